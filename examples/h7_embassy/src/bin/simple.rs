@@ -74,6 +74,8 @@ async fn main(_spawner: Spawner) {
     let mut can = unwrap!(can.into_internal_loopback());
     // let mut can = unwrap!(can.into_normal());
 
+    // The asynchronous feature enables all interrupt sources and line 0 when leaving config mode, what is left is
+    // unmasking the vectors named by `FdCanInstance::interrupt_vector` and the handlers at the end of this file.
     unsafe {
         NVIC::unmask(embassy_stm32::pac::Interrupt::FDCAN1_IT0);
         NVIC::unmask(embassy_stm32::pac::Interrupt::FDCAN1_IT1);
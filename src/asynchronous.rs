@@ -1,19 +1,39 @@
+use crate::config::InterruptMask;
+#[cfg(feature = "embassy")]
+use crate::fdcan::{Operational, PoweredDownMode, Transmit};
 use crate::pac::registers::Fdcan;
 use crate::pac::registers::regs::Ir;
 use crate::pac::{
     FDCAN1_REGISTER_BLOCK_ADDR, FDCAN2_REGISTER_BLOCK_ADDR, FDCAN3_REGISTER_BLOCK_ADDR,
 };
-use crate::{Error, FdCanInstance, FdCanInterrupt};
+#[cfg(feature = "embassy")]
+use crate::{Error, FdCan};
+use crate::{FdCanInstance, FdCanInterrupt};
+#[cfg(feature = "stats")]
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::{AtomicBool, Ordering};
 use embassy_sync::waitqueue::AtomicWaker;
 
 pub(crate) struct State {
     pub(crate) rx_dedicated_waker: AtomicWaker,
+    pub(crate) tx_waker: AtomicWaker,
+    pub(crate) protocol_error_waker: AtomicWaker,
+    /// Set on IR.PEA or IR.PED, cleared by the task waiting for it
+    pub(crate) protocol_error: AtomicBool,
+    /// IR.RF0L and IR.RF1L events serviced here, added to [RxStats::overrun](crate::RxStats::overrun)
+    #[cfg(feature = "stats")]
+    pub(crate) rx_fifo_lost: AtomicU32,
 }
 
 impl State {
     const fn new() -> Self {
         State {
             rx_dedicated_waker: AtomicWaker::new(),
+            tx_waker: AtomicWaker::new(),
+            protocol_error_waker: AtomicWaker::new(),
+            protocol_error: AtomicBool::new(false),
+            #[cfg(feature = "stats")]
+            rx_fifo_lost: AtomicU32::new(0),
         }
     }
 }
@@ -34,8 +54,32 @@ pub(crate) fn state_fdcan3() -> &'static State {
     &STATE
 }
 
-pub fn on_interrupt(instance: FdCanInstance, irq: FdCanInterrupt) {
-    let (state, regs) = match instance {
+/// Services and clears all interrupt sources of the instance, regardless of the line they are routed to, and
+/// returns the flags that were serviced.
+///
+/// Nothing is logged from the interrupt handler itself, as doing so on every interrupt can flood the log
+/// transport at high frame rates. Use the returned flags to log or count interrupt sources at your own discretion.
+pub fn on_interrupt(instance: FdCanInstance, irq: FdCanInterrupt) -> Ir {
+    let (state, regs) = instance_state_regs(instance);
+    service_interrupts(state, regs, InterruptMask::ALL.bits())
+}
+
+/// Services only the interrupt sources routed to `line` by [select_interrupt_line_1](FdCan::select_interrupt_line_1),
+/// flags of the other line are left untouched for its own handler. Returns the flags that were serviced.
+///
+/// Use this instead of [on_interrupt] when both NVIC lines of an instance are enabled.
+pub fn on_interrupt_line(instance: FdCanInstance, line: FdCanInterrupt) -> Ir {
+    let (state, regs) = instance_state_regs(instance);
+    let line1 = regs.ils().read().0;
+    let mask = match line {
+        FdCanInterrupt::Irq0 => !line1,
+        FdCanInterrupt::Irq1 => line1,
+    };
+    service_interrupts(state, regs, mask & InterruptMask::ALL.bits())
+}
+
+fn instance_state_regs(instance: FdCanInstance) -> (&'static State, Fdcan) {
+    match instance {
         FdCanInstance::FdCan1 => (state_fdcan1(), unsafe {
             Fdcan::from_ptr(FDCAN1_REGISTER_BLOCK_ADDR)
         }),
@@ -45,16 +89,125 @@ pub fn on_interrupt(instance: FdCanInstance, irq: FdCanInterrupt) {
         FdCanInstance::FdCan3 => (state_fdcan3(), unsafe {
             Fdcan::from_ptr(FDCAN3_REGISTER_BLOCK_ADDR)
         }),
-    };
+    }
+}
 
-    let ir = regs.ir().read();
-    #[cfg(feature = "defmt")]
-    defmt::trace!("ir: {:?}", ir); // TODO: remove
+/// Wakes tasks waiting on the flags selected by `mask`, clears them and returns the serviced flags.
+fn service_interrupts(state: &State, regs: Fdcan, mask: u32) -> Ir {
+    let ir = Ir(regs.ir().read().0 & mask);
 
     // RX
     if ir.drx() {
         state.rx_dedicated_waker.wake();
     }
 
-    regs.ir().write_value(Ir(u32::MAX >> 2));
+    // TX
+    if ir.tc() || ir.tcf() {
+        state.tx_waker.wake();
+    }
+
+    // Protocol errors, including the ones leading to a protocol exception event
+    if ir.pea() || ir.ped() {
+        state.protocol_error.store(true, Ordering::Release);
+        state.protocol_error_waker.wake();
+    }
+
+    // Message lost, the flag is sticky so any number of frames lost since the last service count as one
+    #[cfg(feature = "stats")]
+    {
+        let lost = u32::from(ir.rfl(0)) + u32::from(ir.rfl(1));
+        if lost > 0 {
+            state.rx_fifo_lost.fetch_add(lost, Ordering::Relaxed);
+        }
+    }
+
+    regs.ir().write_value(ir);
+    ir
+}
+
+/// Message lost events serviced by the interrupt handler of `instance`.
+#[cfg(feature = "stats")]
+pub(crate) fn rx_fifo_lost_count(instance: FdCanInstance) -> u32 {
+    instance_state_regs(instance)
+        .0
+        .rx_fifo_lost
+        .load(Ordering::Relaxed)
+}
+
+#[cfg(feature = "stats")]
+pub(crate) fn reset_rx_fifo_lost_count(instance: FdCanInstance) {
+    instance_state_regs(instance)
+        .0
+        .rx_fifo_lost
+        .store(0, Ordering::Relaxed);
+}
+
+#[cfg(feature = "embassy")]
+impl<M: Transmit> FdCan<M> {
+    /// Waits until all pending transmissions are completed or cancelled (TXBRP == 0), this covers
+    /// dedicated buffers and FIFO/Queue alike.
+    pub async fn flush(&mut self) {
+        core::future::poll_fn(|cx| {
+            self.state.tx_waker.register(cx.waker());
+            if self.is_idle() {
+                core::task::Poll::Ready(())
+            } else {
+                core::task::Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Clean low power entry: waits for all pending transmissions to finish, then requests clock stop and waits
+    /// for it to be acknowledged.
+    ///
+    /// The instance is consumed for the whole sequence, so no frame can be queued between the flush and the clock
+    /// stop request. Clock stop itself also waits for transfers in progress, after the flush this only takes as
+    /// long as it takes the bus to become idle, so the acknowledge is polled with
+    /// [timeout_iterations_long](crate::config::FdCanConfig::timeout_iterations_long).
+    #[allow(clippy::result_large_err)]
+    pub async fn shutdown(mut self) -> Result<FdCan<PoweredDownMode>, (Error, FdCan<M>)> {
+        self.flush().await;
+        if let Err(e) = self.set_power_down_mode(true, self.config.timeout_iterations_long) {
+            return Err((e, self));
+        }
+        Ok(self.into_mode())
+    }
+}
+
+#[cfg(feature = "embassy")]
+impl<M: Operational> FdCan<M> {
+    /// Waits until the node finished bus integration, see [is_bus_integrated](Self::is_bus_integrated).
+    ///
+    /// There is no interrupt for this, PSR.ACT is re-checked after each `pause` completes, e.g.
+    /// `|| embassy_time::Timer::after_micros(20)`. Integration takes 11 bit times on a quiet bus, so a pause in
+    /// that order is enough. `embassy_futures::yield_now` works as well, but keeps the executor from sleeping for
+    /// as long as the node integrates.
+    pub async fn wait_bus_integrated<F: Future<Output = ()>>(
+        &mut self,
+        mut pause: impl FnMut() -> F,
+    ) {
+        while !self.is_bus_integrated() {
+            pause().await;
+        }
+    }
+}
+
+#[cfg(feature = "embassy")]
+impl<M> FdCan<M> {
+    /// Waits until a protocol error is detected in arbitration or data phase (IR.PEA/PED).
+    ///
+    /// Check [protocol_exception_event](Self::protocol_exception_event) afterwards to tell whether the node
+    /// went to bus integrating state because of a protocol exception.
+    pub async fn wait_protocol_error(&mut self) {
+        core::future::poll_fn(|cx| {
+            self.state.protocol_error_waker.register(cx.waker());
+            if self.state.protocol_error.swap(false, Ordering::AcqRel) {
+                core::task::Poll::Ready(())
+            } else {
+                core::task::Poll::Pending
+            }
+        })
+        .await
+    }
 }
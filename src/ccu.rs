@@ -0,0 +1,126 @@
+//! Clock Calibration Unit (H7 only).
+//!
+//! The CCU sits between the kernel clock and all FDCAN instances. It either passes the kernel clock through
+//! (bypass, the usual setup with a crystal) or, on nodes without an accurate oscillator, calibrates the CAN clock
+//! against frames seen on the bus. Its configuration is shared by all instances and only writable while FDCAN1 is
+//! in config mode, hence the access through [FdCan::ccu].
+
+use crate::config::ClockDivider;
+use crate::fdcan::{ConfigMode, Error, FdCan, FdCanInstance};
+use crate::pac;
+use crate::pac::ccu::regs::Ir;
+use core::marker::PhantomData;
+
+/// Calibration state (CCU_CSTAT.CALS)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CalibrationState {
+    NotCalibrated,
+    /// Basic calibration done, the CAN clock is good enough to receive frames
+    Basic,
+    /// Precision calibration done
+    Precision,
+    /// Reserved CALS value
+    Reserved,
+}
+
+/// Snapshot of CCU_CSTAT
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CalibrationStatus {
+    pub state: CalibrationState,
+    /// Time quanta counted during the last calibration
+    pub time_quanta: u16,
+    /// Oscillator clock periods counted during the last calibration
+    pub oscillator_periods: u32,
+}
+
+/// Access to the CCU configuration, borrowed from FDCAN1 in config mode so that writes are not ignored.
+pub struct Ccu<'a> {
+    regs: pac::ccu::Ccu,
+    _can: PhantomData<&'a mut FdCan<ConfigMode>>,
+}
+
+impl Ccu<'_> {
+    /// Passes the kernel clock through to the instances (`true`) or enables clock calibration on the bus (`false`).
+    #[inline]
+    pub fn set_bypass(&mut self, bypass: bool) {
+        self.regs.ccfg().modify(|w| w.set_bcc(bypass));
+    }
+
+    /// Divides the kernel clock before it reaches all instances, applies in bypass mode as well.
+    #[inline]
+    pub fn set_clock_divider(&mut self, div: ClockDivider) {
+        self.regs.ccfg().modify(|w| w.set_cdiv(div as u8));
+    }
+
+    /// Configures calibration: `time_quanta_per_bit` (TQBT, 1 to 32) the instances use, `min_oscillator_periods`
+    /// (OCPM) as the lower bound of the calibration watchdog, and whether calibration measures 32 bit times
+    /// (`long_field`) instead of 8.
+    pub fn configure_calibration(
+        &mut self,
+        time_quanta_per_bit: u8,
+        min_oscillator_periods: u8,
+        long_field: bool,
+    ) {
+        self.regs.ccfg().modify(|w| {
+            w.set_tqbt(time_quanta_per_bit.saturating_sub(1));
+            w.set_ocpm(min_oscillator_periods);
+            w.set_cfl(long_field);
+        });
+    }
+
+    /// Restarts calibration from the not calibrated state.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.regs.ccfg().modify(|w| w.set_swr(true));
+    }
+}
+
+impl FdCan<ConfigMode> {
+    /// Access to the Clock Calibration Unit configuration, returns [Error::WrongInstance] unless called on FDCAN1.
+    pub fn ccu(&mut self) -> Result<Ccu<'_>, Error> {
+        if self.instance != FdCanInstance::FdCan1 {
+            return Err(Error::WrongInstance);
+        }
+        Ok(Ccu {
+            regs: ccu_regs(),
+            _can: PhantomData,
+        })
+    }
+}
+
+impl<M> FdCan<M> {
+    /// Reads the calibration state, valid for every instance.
+    pub fn calibration_status(&self) -> CalibrationStatus {
+        let cstat = ccu_regs().cstat().read();
+        CalibrationStatus {
+            state: match cstat.cals() {
+                0b00 => CalibrationState::NotCalibrated,
+                0b01 => CalibrationState::Basic,
+                0b10 => CalibrationState::Precision,
+                _ => CalibrationState::Reserved,
+            },
+            time_quanta: cstat.tqc(),
+            oscillator_periods: cstat.ocpc(),
+        }
+    }
+
+    /// Returns `true` and clears the flag if the calibration state changed since the last call (CCU_IR.CSC).
+    pub fn calibration_state_changed(&mut self) -> bool {
+        let regs = ccu_regs();
+        let changed = regs.ir().read().csc();
+        if changed {
+            let mut ir = Ir::default();
+            ir.set_csc(true);
+            regs.ir().write_value(ir);
+        }
+        changed
+    }
+}
+
+fn ccu_regs() -> pac::ccu::Ccu {
+    // SAFETY: fixed CCU register block address, read-only accesses and the status flag are harmless, configuration
+    // writes are only reachable through `Ccu`, which borrows FDCAN1 in config mode.
+    unsafe { pac::ccu::Ccu::from_ptr(pac::FDCAN_CCU_REGISTER_BLOCK_ADDR) }
+}
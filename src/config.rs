@@ -1,12 +1,12 @@
 use crate::PoweredDownMode;
 use crate::fdcan::{
-    BusMonitoringMode, Error, ExternalLoopbackMode, NormalOperationMode, RestrictedOperationMode,
-    TestMode,
+    BusMonitoringMode, Error, ExternalLoopbackMode, NormalOperationMode, Operational,
+    RestrictedOperationMode, TestMode,
 };
 use crate::fdcan::{ConfigMode, FdCan, InternalLoopbackMode, LoopbackMode};
 #[cfg(feature = "h7")]
 use crate::message_ram_layout::MessageRamLayout;
-use crate::pac::registers::regs::Ir;
+use crate::pac::registers::regs::{Cccr, Dbtp, Ie, Ir, Nbtp, Psr, Tscc};
 use core::num::{NonZeroU8, NonZeroU16};
 
 /// Configures the bit timings.
@@ -21,7 +21,7 @@ use core::num::{NonZeroU8, NonZeroU16};
 ///
 /// Then copy the `CAN_BUS_TIME` register value from the table and pass it as the `btr`
 /// parameter to this method.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct NominalBitTiming {
     /// Value by which the oscillator frequency is divided for generating the bit time quanta. The bit
@@ -35,6 +35,61 @@ pub struct NominalBitTiming {
     pub sync_jump_width: NonZeroU8,
 }
 impl NominalBitTiming {
+    /// Calculates bit timings for the requested `bitrate` given the FDCAN kernel clock, targeting
+    /// a sample point of 87.5%. Lowest possible prescaler is chosen to get the best resolution.
+    ///
+    /// Returns `None` if `bitrate` cannot be reached exactly with the given clock.
+    pub const fn from_bitrate(clock_hz: u32, bitrate: u32) -> Option<Self> {
+        let Some((prescaler, seg1, seg2)) =
+            calculate_bit_timing(clock_hz, bitrate, 511, 255, 127, 875)
+        else {
+            return None;
+        };
+        let (Some(prescaler), Some(seg1), Some(seg2)) = (
+            NonZeroU16::new(prescaler),
+            NonZeroU8::new(seg1),
+            NonZeroU8::new(seg2),
+        ) else {
+            return None;
+        };
+        Some(Self {
+            prescaler,
+            seg1,
+            seg2,
+            sync_jump_width: seg2,
+        })
+    }
+
+    /// Bitrate these timings yield with the given FDCAN kernel clock, rounded down.
+    ///
+    /// Handy to check a value copied from a bit timing calculator:
+    /// `assert_eq!(timing.bitrate(80_000_000), 500_000)`.
+    #[inline]
+    pub const fn bitrate(&self, clock_hz: u32) -> u32 {
+        bitrate(
+            clock_hz,
+            self.prescaler.get() as u32,
+            self.seg1.get(),
+            self.seg2.get(),
+        )
+    }
+
+    /// Sample point position within the bit in permille, e.g. 875 for 87.5%.
+    #[inline]
+    pub const fn sample_point_permille(&self) -> u16 {
+        sample_point_permille(self.seg1.get(), self.seg2.get())
+    }
+
+    /// NBTP register value, fields hold the configured values minus one.
+    pub(crate) fn register_value(&self) -> Nbtp {
+        let mut w = Nbtp::default();
+        w.set_nbrp(self.nbrp() - 1);
+        w.set_ntseg1(self.ntseg1() - 1);
+        w.set_ntseg2(self.ntseg2() - 1);
+        w.set_nsjw(self.nsjw() - 1);
+        w
+    }
+
     #[inline]
     pub(crate) fn nbrp(&self) -> u16 {
         u16::from(self.prescaler) & 0x1FF
@@ -69,7 +124,7 @@ impl Default for NominalBitTiming {
 
 /// Configures the data bit timings for the FdCan Variable Bitrates.
 /// This is not used when frame_transmit is set to anything other than AllowFdCanAndBRS.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct DataBitTiming {
     /// Tranceiver Delay Compensation
@@ -86,12 +141,65 @@ pub struct DataBitTiming {
     pub sync_jump_width: NonZeroU8,
 }
 impl DataBitTiming {
+    /// Calculates data phase bit timings for the requested `bitrate` given the FDCAN kernel clock,
+    /// targeting a sample point of 80%. Lowest possible prescaler is chosen to get the best resolution.
+    ///
+    /// Returns `None` if `bitrate` cannot be reached exactly with the given clock.
+    pub const fn from_bitrate(clock_hz: u32, bitrate: u32) -> Option<Self> {
+        let Some((prescaler, seg1, seg2)) =
+            calculate_bit_timing(clock_hz, bitrate, 31, 31, 15, 800)
+        else {
+            return None;
+        };
+        let (Some(prescaler), Some(seg1), Some(seg2)) = (
+            NonZeroU8::new(prescaler as u8),
+            NonZeroU8::new(seg1),
+            NonZeroU8::new(seg2),
+        ) else {
+            return None;
+        };
+        Some(Self {
+            transceiver_delay_compensation: false,
+            prescaler,
+            seg1,
+            seg2,
+            sync_jump_width: seg2,
+        })
+    }
+
+    /// Data phase bitrate these timings yield with the given FDCAN kernel clock, rounded down.
+    #[inline]
+    pub const fn bitrate(&self, clock_hz: u32) -> u32 {
+        bitrate(
+            clock_hz,
+            self.prescaler.get() as u32,
+            self.seg1.get(),
+            self.seg2.get(),
+        )
+    }
+
+    /// Data phase sample point position within the bit in permille, e.g. 800 for 80%.
+    #[inline]
+    pub const fn sample_point_permille(&self) -> u16 {
+        sample_point_permille(self.seg1.get(), self.seg2.get())
+    }
+
     // #[inline]
     // fn tdc(&self) -> u8 {
     //     let tsd = self.transceiver_delay_compensation as u8;
     //     //TODO: stm32g4 does not export the TDC field
     //     todo!()
     // }
+    /// DBTP register value, fields hold the configured values minus one.
+    pub(crate) fn register_value(&self) -> Dbtp {
+        let mut w = Dbtp::default();
+        w.set_dbrp(self.dbrp() - 1);
+        w.set_dtseg1(self.dtseg1() - 1);
+        w.set_dtseg2(self.dtseg2() - 1);
+        w.set_dsjw(self.dsjw() - 1);
+        w
+    }
+
     #[inline]
     pub(crate) fn dbrp(&self) -> u8 {
         u8::from(self.prescaler) & 0x1F
@@ -125,11 +233,62 @@ impl Default for DataBitTiming {
     }
 }
 
+/// Bit time is the sync segment (one time quantum) plus seg1 and seg2.
+const fn bitrate(clock_hz: u32, prescaler: u32, seg1: u8, seg2: u8) -> u32 {
+    clock_hz / (prescaler * (1 + seg1 as u32 + seg2 as u32))
+}
+
+/// Sample point sits at the end of seg1.
+const fn sample_point_permille(seg1: u8, seg2: u8) -> u16 {
+    ((1 + seg1 as u32) * 1000 / (1 + seg1 as u32 + seg2 as u32)) as u16
+}
+
+/// Finds the lowest prescaler for which the bit time is a whole number of time quanta and splits it into
+/// (prescaler, seg1, seg2), placing the sample point as close as possible to `sample_point_permille`.
+const fn calculate_bit_timing(
+    clock_hz: u32,
+    bitrate: u32,
+    max_prescaler: u16,
+    max_seg1: u8,
+    max_seg2: u8,
+    sample_point_permille: u32,
+) -> Option<(u16, u8, u8)> {
+    if bitrate == 0 {
+        return None;
+    }
+    let max_tq = 1 + max_seg1 as u32 + max_seg2 as u32;
+    let mut prescaler = 1;
+    while prescaler <= max_prescaler {
+        let divider = (prescaler as u32).saturating_mul(bitrate);
+        if clock_hz.is_multiple_of(divider) {
+            // sync segment + seg1 + seg2
+            let tq = clock_hz / divider;
+            if tq < 4 {
+                return None;
+            }
+            if tq <= max_tq {
+                let mut seg2 = (tq * (1000 - sample_point_permille) + 500) / 1000;
+                if seg2 < 1 {
+                    seg2 = 1;
+                } else if seg2 > max_seg2 as u32 {
+                    seg2 = max_seg2 as u32;
+                }
+                let seg1 = tq - 1 - seg2;
+                if seg1 >= 1 && seg1 <= max_seg1 as u32 {
+                    return Some((prescaler, seg1 as u8, seg2 as u8));
+                }
+            }
+        }
+        prescaler += 1;
+    }
+    None
+}
+
 /// Configures which modes to use
 /// Individual headers can contain a desire to be send via FdCan
 /// or use Bit rate switching. But if this general setting does not allow
 /// that, only classic CAN is used instead.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum FrameTransmissionConfig {
     /// Only allow Classic CAN message Frames
@@ -140,8 +299,19 @@ pub enum FrameTransmissionConfig {
     AllowFdCanAndBRS,
 }
 
+impl FrameTransmissionConfig {
+    /// CCCR.FDOE and CCCR.BRSE for this setting
+    const fn cccr_bits(self) -> (bool, bool) {
+        match self {
+            FrameTransmissionConfig::ClassicCanOnly => (false, false),
+            FrameTransmissionConfig::AllowFdCan => (true, false),
+            FrameTransmissionConfig::AllowFdCanAndBRS => (true, true),
+        }
+    }
+}
+
 ///
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ClockDivider {
     /// Divide by 1
@@ -179,7 +349,7 @@ pub enum ClockDivider {
 }
 
 /// Prescaler of the Timestamp counter
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum TimestampPrescaler {
     /// 1
@@ -218,7 +388,7 @@ pub enum TimestampPrescaler {
 
 /// Selects the source of the Timestamp counter.
 /// With CAN FD an external counter is required for timestamp generation (TSS = “10”) (Bosch MCAN: page 24)
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum TimestampSource {
     /// The Timestamp counter is disabled
@@ -230,8 +400,25 @@ pub enum TimestampSource {
     FromTIM3,
 }
 
+impl TimestampSource {
+    fn register_value(&self) -> Tscc {
+        let (tcp, tss) = match self {
+            TimestampSource::None => (0, 0b00),
+            TimestampSource::Prescaler(p) => (*p as u8, 0b01),
+            TimestampSource::FromTIM3 => (0, 0b10),
+        };
+        let mut w = Tscc::default();
+        w.set_tcp(tcp);
+        w.set_tss(tss);
+        w
+    }
+}
+
 /// How to handle frames in the global filter
-#[derive(Clone, Copy, Debug)]
+///
+/// Non-matching frames can only be stored into one of the RX FIFOs or rejected, storing them into a dedicated
+/// RX buffer is only possible through an explicit filter element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum NonMatchingFilter {
     /// Frames will go to Fifo0 when they do no match any specific filter
@@ -242,8 +429,24 @@ pub enum NonMatchingFilter {
     Reject = 0b11,
 }
 
+impl NonMatchingFilter {
+    const fn from_bits(bits: u8) -> Self {
+        match bits & 0b11 {
+            0b00 => NonMatchingFilter::IntoRxFifo0,
+            0b01 => NonMatchingFilter::IntoRxFifo1,
+            // 0b10 is documented as reject as well
+            _ => NonMatchingFilter::Reject,
+        }
+    }
+}
+
 /// How to handle frames which do not match a specific filter
-#[derive(Clone, Copy, Debug)]
+///
+/// Maps to the GFC register on H7 (MCAN core). On G0/G4 the same fields live in RXGFC, which additionally
+/// holds the filter list sizes (LSS/LSE) and FIFO operation modes, those are configured through the RAM layout
+/// and are not part of the global filter. There is no separate timestamp or anti-spoofing control in either of
+/// them, sync message timestamping is configured per filter element (SSYNC/ESYNC).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct GlobalFilter {
     /// How to handle non-matching standard frames
@@ -302,8 +505,116 @@ impl Default for GlobalFilter {
     }
 }
 
+/// Set of interrupt sources with named setters, bit positions match IR, IE and ILS.
+///
+/// Converts into [Ir], e.g. for [FdCanConfig::select_interrupt_line_1]:
+/// ```ignore
+/// let line1 = InterruptMask::NONE.bus_off(true).error_passive(true);
+/// config.select_interrupt_line_1(line1.into())
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InterruptMask(u32);
+
+macro_rules! interrupt_mask_bits {
+    ($($(#[$doc:meta])* $name:ident = $bit:literal,)*) => {
+        impl InterruptMask {
+            $(
+                $(#[$doc])*
+                #[inline]
+                pub const fn $name(self, enabled: bool) -> Self {
+                    if enabled {
+                        Self(self.0 | (1 << $bit))
+                    } else {
+                        Self(self.0 & !(1 << $bit))
+                    }
+                }
+            )*
+        }
+    };
+}
+
+interrupt_mask_bits!(
+    /// Rx FIFO 0 new message (RF0N)
+    rx_fifo0_new_message = 0,
+    /// Rx FIFO 0 watermark reached (RF0W)
+    rx_fifo0_watermark = 1,
+    /// Rx FIFO 0 full (RF0F)
+    rx_fifo0_full = 2,
+    /// Rx FIFO 0 message lost (RF0L)
+    rx_fifo0_message_lost = 3,
+    /// Rx FIFO 1 new message (RF1N)
+    rx_fifo1_new_message = 4,
+    /// Rx FIFO 1 watermark reached (RF1W)
+    rx_fifo1_watermark = 5,
+    /// Rx FIFO 1 full (RF1F)
+    rx_fifo1_full = 6,
+    /// Rx FIFO 1 message lost (RF1L)
+    rx_fifo1_message_lost = 7,
+    /// High priority message (HPM)
+    high_priority_message = 8,
+    /// Transmission completed (TC)
+    tx_complete = 9,
+    /// Transmission cancellation finished (TCF)
+    tx_cancellation_finished = 10,
+    /// Tx FIFO empty (TFE)
+    tx_fifo_empty = 11,
+    /// Tx Event FIFO new entry (TEFN)
+    tx_event_fifo_new_entry = 12,
+    /// Tx Event FIFO watermark reached (TEFW)
+    tx_event_fifo_watermark = 13,
+    /// Tx Event FIFO full (TEFF)
+    tx_event_fifo_full = 14,
+    /// Tx Event FIFO element lost (TEFL)
+    tx_event_fifo_element_lost = 15,
+    /// Timestamp wraparound (TSW)
+    timestamp_wraparound = 16,
+    /// Message RAM access failure (MRAF)
+    message_ram_access_failure = 17,
+    /// Timeout occurred (TOO)
+    timeout_occurred = 18,
+    /// Message stored to dedicated Rx buffer (DRX)
+    rx_buffer_new_message = 19,
+    /// Error logging overflow (ELO)
+    error_logging_overflow = 22,
+    /// Error passive (EP)
+    error_passive = 23,
+    /// Warning status (EW)
+    error_warning = 24,
+    /// Bus off status (BO)
+    bus_off = 25,
+    /// Watchdog interrupt (WDI)
+    watchdog = 26,
+    /// Protocol error in arbitration phase (PEA)
+    protocol_error_arbitration = 27,
+    /// Protocol error in data phase (PED)
+    protocol_error_data = 28,
+    /// Access to reserved address (ARA)
+    access_to_reserved_address = 29,
+);
+
+impl InterruptMask {
+    /// No interrupt sources.
+    pub const NONE: Self = Self(0);
+    /// All interrupt sources.
+    pub const ALL: Self = Self(u32::MAX >> 2);
+
+    /// Raw register value.
+    #[inline]
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<InterruptMask> for Ir {
+    #[inline]
+    fn from(mask: InterruptMask) -> Self {
+        Ir(mask.0)
+    }
+}
+
 /// FdCan Config Struct
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct FdCanConfig {
     /// Nominal Bit Timings
@@ -337,13 +648,24 @@ pub struct FdCanConfig {
     pub edge_filtering: bool,
     /// Enables protocol exception handling
     pub protocol_exception_handling: bool,
+    /// Enables 16-bit message markers (CCCR.WMM), needed to tell apart more than 256 outstanding transmissions.
+    /// The upper marker byte replaces the upper byte of the Tx event timestamp.
+    #[cfg(feature = "h7")]
+    pub wide_message_marker: bool,
     /// Sets the general clock divider for this FdCAN instance
     pub clock_divider: ClockDivider,
+    /// Interrupt sources to enable (IE), use [InterruptMask] to build it. The `asynchronous` feature overrides
+    /// this with all sources it handles.
+    pub interrupt_enable: Ir,
     /// This sets the interrupts for each interrupt line of the FdCan (FDCAN_INT0/1)
     /// Each interrupt set to 0 is set to line_0, each set to 1 is set to line_1.
     /// NOTE: This does not enable or disable the interrupt, but merely configure
     /// them to which interrupt the WOULD trigger if they are enabled.
     pub interrupt_line_config: Ir,
+    /// Enables interrupt lines FDCAN_INT0 and FDCAN_INT1 (ILE.EINT0/EINT1). Without it, enabled and correctly
+    /// assigned interrupts never reach the NVIC. Both are disabled by default, the `asynchronous` feature always
+    /// enables line 0.
+    pub interrupt_lines_enabled: (bool, bool),
     /// Sets the timestamp source
     pub timestamp_source: TimestampSource,
     /// Configures the Global Filter
@@ -356,11 +678,74 @@ pub struct FdCanConfig {
     /// How long to wait when entering PowerDownMode or aborting before returning an error.
     /// Should be longer than the longest frame transmission time to not false trigger the timeout, assuming all transmissions are
     /// aborted before entering power down, and just one might need to be completed.
+    ///
+    /// This is only the default, operations that can take wildly different time have `_with_timeout` variants, e.g.
+    /// [abort_with_timeout](crate::FdCan::abort_with_timeout) and
+    /// [into_powered_down_with_timeout](crate::FdCan::into_powered_down_with_timeout).
     pub timeout_iterations_long: u32,
+    /// How long to wait for INIT to be synchronized between clock domains when entering or leaving initialization,
+    /// polling only starts after a fixed synchronization delay.
     pub timeout_iterations_short: u32,
 }
 
 impl FdCanConfig {
+    /// Classic CAN only configuration with nominal bit timing calculated for `bitrate`, everything else is default.
+    ///
+    /// Returns `None` if `bitrate` cannot be reached exactly with the given FDCAN kernel clock.
+    pub fn classic(clock_hz: u32, bitrate: u32) -> Option<Self> {
+        let nbtr = NominalBitTiming::from_bitrate(clock_hz, bitrate)?;
+        Some(
+            Self::default()
+                .set_frame_transmit(FrameTransmissionConfig::ClassicCanOnly)
+                .set_nominal_bit_timing(nbtr),
+        )
+    }
+
+    /// CAN FD with bit rate switching configuration with nominal and data bit timings calculated for
+    /// `nominal_bitrate` and `data_bitrate`, everything else is default.
+    ///
+    /// Returns `None` if either bitrate cannot be reached exactly with the given FDCAN kernel clock.
+    pub fn fd_brs(clock_hz: u32, nominal_bitrate: u32, data_bitrate: u32) -> Option<Self> {
+        let nbtr = NominalBitTiming::from_bitrate(clock_hz, nominal_bitrate)?;
+        let dbtr = DataBitTiming::from_bitrate(clock_hz, data_bitrate)?;
+        Some(
+            Self::default()
+                .set_frame_transmit(FrameTransmissionConfig::AllowFdCanAndBRS)
+                .set_nominal_bit_timing(nbtr)
+                .set_data_bit_timing(dbtr),
+        )
+    }
+
+    /// Classic CAN at 125 kbit/s.
+    pub fn classic_125k(clock_hz: u32) -> Option<Self> {
+        Self::classic(clock_hz, 125_000)
+    }
+
+    /// Classic CAN at 250 kbit/s.
+    pub fn classic_250k(clock_hz: u32) -> Option<Self> {
+        Self::classic(clock_hz, 250_000)
+    }
+
+    /// Classic CAN at 500 kbit/s.
+    pub fn classic_500k(clock_hz: u32) -> Option<Self> {
+        Self::classic(clock_hz, 500_000)
+    }
+
+    /// Classic CAN at 1 Mbit/s.
+    pub fn classic_1m(clock_hz: u32) -> Option<Self> {
+        Self::classic(clock_hz, 1_000_000)
+    }
+
+    /// CAN FD with 500 kbit/s arbitration phase and 2 Mbit/s data phase.
+    pub fn fd_500k_2m(clock_hz: u32) -> Option<Self> {
+        Self::fd_brs(clock_hz, 500_000, 2_000_000)
+    }
+
+    /// CAN FD with 1 Mbit/s arbitration phase and 5 Mbit/s data phase.
+    pub fn fd_1m_5m(clock_hz: u32) -> Option<Self> {
+        Self::fd_brs(clock_hz, 1_000_000, 5_000_000)
+    }
+
     /// Configures the bit timings.
     #[inline]
     pub const fn set_nominal_bit_timing(mut self, btr: NominalBitTiming) -> Self {
@@ -392,6 +777,13 @@ impl FdCanConfig {
     /// This feature looses up burst transmissions coming from a single node and it protects against
     /// "babbling idiot" scenarios where the application program erroneously requests too many
     /// transmissions.
+    ///
+    /// The pause of two bit times follows a successful transmission and delays the start of the next one, it is
+    /// not part of the transmission: the request is completed (TXBRP cleared, TXBTO set) at the end of the frame,
+    /// before the pause. So [is_idle](crate::FdCan::is_idle) and `flush` report completion without waiting for
+    /// the pause, a frame requested right after only goes out once it elapsed. With
+    /// [automatic retransmission](Self::set_automatic_retransmit) disabled, a failed single attempt also completes
+    /// the request (TXBRP cleared, TXBTO not set), no pause follows it as it was not successful.
     #[inline]
     pub const fn set_transmit_pause(mut self, enabled: bool) -> Self {
         self.transmit_pause = enabled;
@@ -427,14 +819,36 @@ impl FdCanConfig {
         self
     }
 
+    /// Enables 16-bit message markers
+    #[cfg(feature = "h7")]
+    #[inline]
+    pub const fn set_wide_message_marker(mut self, wmm: bool) -> Self {
+        self.wide_message_marker = wmm;
+        self
+    }
+
+    /// Enables the given interrupt sources, all others are disabled. Use [InterruptMask] to build `sources`.
+    #[inline]
+    pub const fn set_interrupt_enable(mut self, sources: Ir) -> Self {
+        self.interrupt_enable = sources;
+        self
+    }
+
     /// Selects Interrupt Line 1 for the given interrupts. Interrupt Line 0 is
-    /// selected for all other interrupts
+    /// selected for all other interrupts. Use [InterruptMask] to build `l1int`.
     #[inline]
     pub const fn select_interrupt_line_1(mut self, l1int: Ir) -> Self {
         self.interrupt_line_config = l1int;
         self
     }
 
+    /// Enables or disables interrupt lines 0 and 1.
+    #[inline]
+    pub const fn enable_interrupt_lines(mut self, line0: bool, line1: bool) -> Self {
+        self.interrupt_lines_enabled = (line0, line1);
+        self
+    }
+
     /// Sets the general clock divider for this FdCAN instance
     #[inline]
     pub const fn set_clock_divider(mut self, div: ClockDivider) -> Self {
@@ -455,6 +869,14 @@ impl FdCanConfig {
         self.global_filter = filter;
         self
     }
+
+    /// Sets how long to poll for CCCR.INIT to be synchronized when entering or leaving initialization,
+    /// see [timeout_iterations_short](Self::timeout_iterations_short). Increase it for slow FDCAN kernel clocks.
+    #[inline]
+    pub const fn set_init_timeout_iterations(mut self, timeout_iterations: u32) -> Self {
+        self.timeout_iterations_short = timeout_iterations;
+        self
+    }
 }
 
 impl Default for FdCanConfig {
@@ -468,8 +890,12 @@ impl Default for FdCanConfig {
             frame_transmit: FrameTransmissionConfig::ClassicCanOnly,
             non_iso_mode: false,
             edge_filtering: false,
+            interrupt_enable: Ir(0),
             interrupt_line_config: Ir(0),
+            interrupt_lines_enabled: (false, false),
             protocol_exception_handling: true,
+            #[cfg(feature = "h7")]
+            wide_message_marker: false,
             clock_divider: ClockDivider::_1,
             timestamp_source: TimestampSource::None,
             global_filter: GlobalFilter::default(),
@@ -483,6 +909,7 @@ impl Default for FdCanConfig {
 
 impl FdCan<ConfigMode> {
     #[inline]
+    #[allow(clippy::result_large_err)]
     pub fn into_internal_loopback(
         mut self,
     ) -> Result<FdCan<InternalLoopbackMode>, (Error, FdCan<ConfigMode>)> {
@@ -495,6 +922,7 @@ impl FdCan<ConfigMode> {
 
     /// Moves out of ConfigMode and into ExternalLoopbackMode
     #[inline]
+    #[allow(clippy::result_large_err)]
     pub fn into_external_loopback(
         mut self,
     ) -> Result<FdCan<ExternalLoopbackMode>, (Error, FdCan<ConfigMode>)> {
@@ -507,6 +935,7 @@ impl FdCan<ConfigMode> {
 
     /// Moves out of ConfigMode and into RestrictedOperationMode
     #[inline]
+    #[allow(clippy::result_large_err)]
     pub fn into_restricted(
         mut self,
     ) -> Result<FdCan<RestrictedOperationMode>, (Error, FdCan<ConfigMode>)> {
@@ -519,6 +948,7 @@ impl FdCan<ConfigMode> {
 
     /// Moves out of ConfigMode and into NormalOperationMode
     #[inline]
+    #[allow(clippy::result_large_err)]
     pub fn into_normal(mut self) -> Result<FdCan<NormalOperationMode>, (Error, FdCan<ConfigMode>)> {
         self.set_normal_operations(true);
         if let Err(e) = self.leave_init_mode() {
@@ -529,6 +959,7 @@ impl FdCan<ConfigMode> {
 
     /// Moves out of ConfigMode and into BusMonitoringMode
     #[inline]
+    #[allow(clippy::result_large_err)]
     pub fn into_bus_monitoring(
         mut self,
     ) -> Result<FdCan<BusMonitoringMode>, (Error, FdCan<ConfigMode>)> {
@@ -541,6 +972,7 @@ impl FdCan<ConfigMode> {
 
     /// Moves out of ConfigMode and into TestMode
     #[inline]
+    #[allow(clippy::result_large_err)]
     pub fn into_test_mode(mut self) -> Result<FdCan<TestMode>, (Error, FdCan<ConfigMode>)> {
         self.set_test_mode(true);
         if let Err(e) = self.leave_init_mode() {
@@ -550,12 +982,32 @@ impl FdCan<ConfigMode> {
     }
 
     /// Moves out of ConfigMode and into PoweredDownMode
+    ///
+    /// FDCAN on H7 and G0 (core release 3.2.x) has no clock stop wake enable (CCCR.CSWE is only present in later
+    /// M_CAN releases), so the peripheral cannot wake the system on bus activity by itself. To sleep with the
+    /// CAN clock stopped and wake on a frame, configure an EXTI falling edge interrupt on the FDCAN RX pin
+    /// (start of frame is dominant), then bring the instance back through [FdCan::into_config_mode].
+    /// The kernel clock can only be gated in RCC once all instances are powered down, see
+    /// [FdCanInstances::disable](crate::FdCanInstances::disable).
     #[inline]
+    #[allow(clippy::result_large_err)]
     pub fn into_powered_down(
+        self,
+    ) -> Result<FdCan<PoweredDownMode>, (Error, FdCan<PoweredDownMode>)> {
+        let timeout_iterations = self.config.timeout_iterations_long;
+        self.into_powered_down_with_timeout(timeout_iterations)
+    }
+
+    /// Same as [into_powered_down](Self::into_powered_down), but waits at most `timeout_iterations` for clock stop
+    /// to be acknowledged instead of [FdCanConfig::timeout_iterations_long]. Clock stop is only acknowledged after
+    /// pending transmissions are done, so the right value depends on how many frames can still be queued.
+    #[allow(clippy::result_large_err)]
+    pub fn into_powered_down_with_timeout(
         mut self,
+        timeout_iterations: u32,
     ) -> Result<FdCan<PoweredDownMode>, (Error, FdCan<PoweredDownMode>)> {
         // TODO: handle error better here, the only reason for it is if timeout is too short, but PoweredDownMode should be reached eventually anyway
-        if let Err(e) = self.set_power_down_mode(true) {
+        if let Err(e) = self.set_power_down_mode(true, timeout_iterations) {
             return Err((e, self.into_mode()));
         }
         if let Err(e) = self.leave_init_mode() {
@@ -571,24 +1023,18 @@ impl FdCan<ConfigMode> {
         #[cfg(feature = "asynchronous")]
         self.enable_interrupts();
 
-        self.can.cccr().modify(|w| w.set_cce(false));
-        self.can.cccr().modify(|w| w.set_init(false));
-        crate::util::checked_wait(
-            || self.can.cccr().read().init(),
-            self.config.timeout_iterations_short,
-        )?;
-        Ok(())
+        self.exit_init_mode(self.config.timeout_iterations_short)
     }
 
     #[inline]
     #[cfg(feature = "asynchronous")]
     fn enable_interrupts(&mut self) {
-        use crate::pac::registers::regs::{Ie, Txbcie, Txbtie};
+        use crate::pac::registers::regs::{Txbcie, Txbtie};
         // Enable all interrupts when this crate handles them
-        self.can.ie().write_value(Ie(u32::MAX >> 2));
+        self.set_interrupt_enable(Ir(InterruptMask::ALL.bits()));
         self.can.txbtie().write_value(Txbtie(u32::MAX));
         self.can.txbcie().write_value(Txbcie(u32::MAX));
-        self.can.ile().modify(|w| w.set_eint0(true));
+        self.enable_interrupt_lines(true, self.config.interrupt_lines_enabled.1);
     }
 
     /// Applies the settings of a new FdCanConfig See [`FdCanConfig`]
@@ -599,15 +1045,41 @@ impl FdCan<ConfigMode> {
         self.set_automatic_retransmit(config.automatic_retransmit);
         self.set_transmit_pause(config.transmit_pause);
         self.set_frame_transmit(config.frame_transmit);
+        self.set_interrupt_enable(config.interrupt_enable);
         self.select_interrupt_line_1(config.interrupt_line_config);
+        self.enable_interrupt_lines(
+            config.interrupt_lines_enabled.0,
+            config.interrupt_lines_enabled.1,
+        );
         self.set_non_iso_mode(config.non_iso_mode);
         self.set_edge_filtering(config.edge_filtering);
         self.set_protocol_exception_handling(config.protocol_exception_handling);
+        #[cfg(feature = "h7")]
+        self.set_wide_message_marker(config.wide_message_marker);
         self.set_global_filter(config.global_filter);
+        self.set_timestamp_counter_source(config.timestamp_source);
         #[cfg(feature = "h7")]
         self.set_layout(config.layout);
     }
 
+    /// Configures a classic CAN only node in one call: nominal bit timing for `bitrate` is calculated from
+    /// `clock_hz`, everything else is default. Call [into_normal](Self::into_normal) afterwards to join the bus.
+    ///
+    /// Returns [Error::UnsupportedBitrate] if `bitrate` cannot be reached exactly, nothing is changed then.
+    #[cfg(feature = "h7")]
+    pub fn setup_classic(
+        &mut self,
+        clock_hz: u32,
+        bitrate: u32,
+        layout: MessageRamLayout,
+    ) -> Result<(), Error> {
+        let mut config =
+            FdCanConfig::classic(clock_hz, bitrate).ok_or(Error::UnsupportedBitrate)?;
+        config.layout = layout;
+        self.apply_config(config);
+        Ok(())
+    }
+
     /// Configures the bit timings.
     ///
     /// You can use <http://www.bittiming.can-wiki.info/> to calculate the `btr` parameter. Enter
@@ -622,28 +1094,14 @@ impl FdCan<ConfigMode> {
     /// parameter to this method.
     #[inline]
     pub fn set_nominal_bit_timing(&mut self, btr: NominalBitTiming) {
-        self.config.nbtr = btr;
-
-        self.can.nbtp().write(|w| {
-            w.set_nbrp(btr.nbrp() - 1);
-            w.set_ntseg1(btr.ntseg1() - 1);
-            w.set_ntseg2(btr.ntseg2() - 1);
-            w.set_nsjw(btr.nsjw() - 1);
-        });
+        self.write_nominal_bit_timing(btr);
     }
 
     /// Configures the data bit timings for the FdCan Variable Bitrates.
     /// This is not used when frame_transmit is set to anything other than AllowFdCanAndBRS.
     #[inline]
     pub fn set_data_bit_timing(&mut self, btr: DataBitTiming) {
-        self.config.dbtr = btr;
-
-        self.can.dbtp().write(|w| {
-            w.set_dbrp(btr.dbrp() - 1);
-            w.set_dtseg1(btr.dtseg1() - 1);
-            w.set_dtseg2(btr.dtseg2() - 1);
-            w.set_dsjw(btr.dsjw() - 1);
-        });
+        self.write_data_bit_timing(btr);
     }
 
     /// Enables or disables automatic retransmission of messages
@@ -666,7 +1124,7 @@ impl FdCan<ConfigMode> {
         self.config.transmit_pause = enabled;
     }
 
-    /// Configures non-iso mode. See [`FdCanConfig::set_non_iso_mode`]
+    /// Configures non-iso mode. See [`FdCanConfig::set_non_iso_mode`], read back with [FdCan::non_iso_mode].
     #[inline]
     pub fn set_non_iso_mode(&mut self, enabled: bool) {
         self.can.cccr().modify(|w| w.set_niso(enabled));
@@ -684,11 +1142,7 @@ impl FdCan<ConfigMode> {
     /// [`FdCanConfig::set_frame_transmit`]
     #[inline]
     pub fn set_frame_transmit(&mut self, fts: FrameTransmissionConfig) {
-        let (fdoe, brse) = match fts {
-            FrameTransmissionConfig::ClassicCanOnly => (false, false),
-            FrameTransmissionConfig::AllowFdCan => (true, false),
-            FrameTransmissionConfig::AllowFdCanAndBRS => (true, true),
-        };
+        let (fdoe, brse) = fts.cccr_bits();
 
         self.can.cccr().modify(|w| {
             w.set_fdoe(fdoe);
@@ -698,6 +1152,15 @@ impl FdCan<ConfigMode> {
         self.config.frame_transmit = fts;
     }
 
+    /// Enables the given interrupt sources (IE), all others are disabled. See
+    /// [`FdCanConfig::interrupt_enable`]
+    #[inline]
+    pub fn set_interrupt_enable(&mut self, sources: Ir) {
+        self.can.ie().write_value(Ie(sources.0));
+
+        self.config.interrupt_enable = sources;
+    }
+
     /// Selects Interrupt Line 1 for the given interrupts. Interrupt Line 0 is
     /// selected for all other interrupts. See
     /// [`FdCanConfig::select_interrupt_line_1`]
@@ -707,7 +1170,20 @@ impl FdCan<ConfigMode> {
         self.config.interrupt_line_config = l1int;
     }
 
-    /// Sets the protocol exception handling on/off
+    /// Enables or disables interrupt lines FDCAN_INT0 and FDCAN_INT1 (ILE), see
+    /// [`FdCanConfig::interrupt_lines_enabled`]. Interrupt sources also need to be enabled and assigned to a line
+    /// with [select_interrupt_line_1](Self::select_interrupt_line_1).
+    #[inline]
+    pub fn enable_interrupt_lines(&mut self, line0: bool, line1: bool) {
+        self.can.ile().write(|w| {
+            w.set_eint0(line0);
+            w.set_eint1(line1);
+        });
+
+        self.config.interrupt_lines_enabled = (line0, line1);
+    }
+
+    /// Sets the protocol exception handling on/off, read back with [FdCan::protocol_exception_handling].
     #[inline]
     pub fn set_protocol_exception_handling(&mut self, enabled: bool) {
         self.can.cccr().modify(|w| w.set_pxhd(!enabled));
@@ -715,37 +1191,92 @@ impl FdCan<ConfigMode> {
         self.config.protocol_exception_handling = enabled;
     }
 
+    /// Enables or disables 16-bit message markers (CCCR.WMM), without it the upper marker byte is ignored.
+    #[cfg(feature = "h7")]
+    #[inline]
+    pub fn set_wide_message_marker(&mut self, enabled: bool) {
+        self.can.cccr().modify(|w| w.set_wmm(enabled));
+
+        self.config.wide_message_marker = enabled;
+    }
+
     /// Configures and resets the timestamp counter
     #[inline]
     pub fn set_timestamp_counter_source(&mut self, select: TimestampSource) {
-        let (tcp, tss) = match select {
-            TimestampSource::None => (0, 0b00),
-            TimestampSource::Prescaler(p) => (p as u8, 0b01),
-            TimestampSource::FromTIM3 => (0, 0b10),
-        };
-        self.can.tscc().write(|w| {
-            w.set_tcp(tcp);
-            w.set_tss(tss);
-        });
+        self.can.tscc().write_value(select.register_value());
 
         self.config.timestamp_source = select;
     }
 
+    /// Starts the message RAM watchdog from `start_value` (RWD.WDC), 0 disables it.
+    ///
+    /// The watchdog counts down in units of the message RAM clock while a message RAM access requested by the core
+    /// is pending, and sets IR.WDI when it reaches zero. An expiry usually means the message RAM clock is
+    /// misconfigured or stopped, see [ram_watchdog_value](FdCan::ram_watchdog_value).
+    #[inline]
+    pub fn set_ram_watchdog(&mut self, start_value: u8) {
+        self.can.rwd().modify(|w| w.set_wdc(start_value));
+    }
+
     /// Configures the global filter settings
+    ///
+    /// GFC is only writable while CCCR.CCE is set, use
+    /// [reconfigure_global_filter](FdCan::reconfigure_global_filter) to change it after leaving config mode.
     #[inline]
     pub fn set_global_filter(&mut self, filter: GlobalFilter) {
-        self.can.gfc().modify(|w| {
-            w.set_anfs(filter.handle_standard_frames as u8);
-            w.set_anfe(filter.handle_extended_frames as u8);
-            w.set_rrfs(filter.reject_remote_standard_frames);
-            w.set_rrfe(filter.reject_remote_extended_frames);
-        });
+        self.write_global_filter(filter);
+    }
+
+    /// Zeroes the message RAM region covered by the currently applied layout, leaving RAM of other instances
+    /// untouched.
+    ///
+    /// Meant for [relayout](MessageRamLayout::relayout) after entering config mode with
+    /// [into_config_mode_preserving_ram](FdCan::into_config_mode_preserving_ram): leftovers of the old layout
+    /// would otherwise be picked up as filter elements or stale frames by the new one.
+    ///
+    /// Returns [Error::LayoutNotApplied] if no layout was applied, there is no region to clear then.
+    #[cfg(feature = "h7")]
+    pub fn clear_layout_ram(&mut self) -> Result<(), Error> {
+        self.init_ram_region(0)
+    }
+
+    /// Writes `pattern` to every message RAM word covered by the currently applied layout, from its lowest to
+    /// its highest address, leaving RAM of other instances untouched.
+    ///
+    /// Scoped counterpart of the whole RAM zeroing done when entering config mode: useful to create valid ECC
+    /// checksums for this instance only after a partial reset, or to spot reads of elements that were never
+    /// written by filling the region with e.g. `0xDEAD_BEEF`.
+    ///
+    /// Returns [Error::LayoutNotApplied] if no layout was applied.
+    #[cfg(feature = "h7")]
+    pub fn init_ram_region(&mut self, pattern: u32) -> Result<(), Error> {
+        if self.config.layout.is_empty() {
+            return Err(Error::LayoutNotApplied);
+        }
+        let (start, end) = self.config.layout.region();
+        for offset in start as usize..end as usize {
+            // SAFETY: the region was allocated by the builder within message RAM, in config mode the core
+            // does not access it.
+            unsafe { core::ptr::write_volatile(self.msg_ram.word_ptr(offset), pattern) };
+        }
+        Ok(())
     }
 
-    /// Configures RAM layout for this instance
+    /// Configures RAM layout for this instance and writes filters allocated with initial contents.
+    ///
+    /// Filter lists are located by the layout, so this has to happen before any filter element is written,
+    /// [configure_standard_filters](Self::configure_standard_filters) and friends return
+    /// [Error::LayoutNotApplied] otherwise.
     #[cfg(feature = "h7")]
     #[inline]
     pub fn set_layout(&mut self, layout: MessageRamLayout) {
+        debug_assert!(
+            layout.rx_fifo0_data_size.config_register_consistent()
+                && layout.rx_fifo1_data_size.config_register_consistent()
+                && layout.rx_buffers_data_size.config_register_consistent()
+                && layout.tx_buffers_data_size.config_register_consistent(),
+            "element size register value disagrees with the stride used to address message RAM"
+        );
         self.config.layout = layout;
         self.can.sidfc().modify(|w| {
             w.set_flssa(layout.eleven_bit_filters_addr);
@@ -787,5 +1318,616 @@ impl FdCan<ConfigMode> {
             w.set_tmsa(layout.trigger_memory_addr);
             w.set_tme(layout.trigger_memory_len);
         });
+        // Lengths were allocated from these very slices, so bounds checks can't fail.
+        let _ = self.configure_standard_filters(0, layout.eleven_bit_filters_init);
+        let _ = self.configure_extended_filters(0, layout.twenty_nine_bit_filters_init);
+    }
+}
+
+impl<M: Operational> FdCan<M> {
+    /// Changes nominal and data bit timings at runtime by briefly entering initialization mode.
+    ///
+    /// Message RAM contents (filters, layout, staged buffers) are left untouched, but setting CCE resets
+    /// the Tx and Rx handlers (TXBRP, TXFQS, RXFnS, TXEFS, etc.), so pending transmissions are dropped.
+    /// Bus participation resumes after 11 consecutive recessive bits are seen with the new timings.
+    pub fn reconfigure_timing(
+        &mut self,
+        nbtr: NominalBitTiming,
+        dbtr: DataBitTiming,
+    ) -> Result<(), Error> {
+        self.enter_init_mode(self.config.timeout_iterations_short)?;
+        self.write_nominal_bit_timing(nbtr);
+        self.write_data_bit_timing(dbtr);
+        self.exit_init_mode(self.config.timeout_iterations_short)
+    }
+
+    /// Changes the global filter at runtime by briefly entering initialization mode, e.g. to switch from storing
+    /// non-matching frames in FIFO1 to rejecting them.
+    ///
+    /// Same caveats as [reconfigure_timing](Self::reconfigure_timing): message RAM is left untouched, but pending
+    /// transmissions are dropped and the node has to integrate into the bus again.
+    pub fn reconfigure_global_filter(&mut self, filter: GlobalFilter) -> Result<(), Error> {
+        self.enter_init_mode(self.config.timeout_iterations_short)?;
+        self.write_global_filter(filter);
+        self.exit_init_mode(self.config.timeout_iterations_short)
+    }
+
+    /// Returns `true` once the node finished bus integration (PSR.ACT is no longer "synchronizing"), i.e. saw
+    /// 11 consecutive recessive bits after leaving initialization or after a protocol exception.
+    ///
+    /// Until then transmission requests are accepted but sit idle. Reading PSR resets its last error code fields
+    /// and PXE, the driver keeps them for [possible_iso_mismatch](Self::possible_iso_mismatch) and the other
+    /// readers of those fields.
+    #[inline]
+    pub fn is_bus_integrated(&mut self) -> bool {
+        const SYNCHRONIZING: u8 = 0b00;
+        self.read_psr_status().act() != SYNCHRONIZING
+    }
+
+    /// Polls until the node finished bus integration, see [is_bus_integrated](Self::is_bus_integrated).
+    ///
+    /// Returns [Error::Timeout] if the node is still integrating after `timeout_iterations`, e.g. because the
+    /// bus is stuck dominant or the transceiver is in standby.
+    pub fn wait_bus_integrated_blocking(&mut self, timeout_iterations: u32) -> Result<(), Error> {
+        crate::util::checked_wait(|| !self.is_bus_integrated(), timeout_iterations)
+    }
+}
+
+impl<M> FdCan<M> {
+    /// Must only be called while CCCR.CCE = 1.
+    #[inline]
+    fn write_nominal_bit_timing(&mut self, btr: NominalBitTiming) {
+        self.config.nbtr = btr;
+
+        self.can.nbtp().write_value(btr.register_value());
+    }
+
+    /// Must only be called while CCCR.CCE = 1.
+    #[inline]
+    fn write_data_bit_timing(&mut self, btr: DataBitTiming) {
+        self.config.dbtr = btr;
+
+        self.can.dbtp().write_value(btr.register_value());
+    }
+
+    /// Must only be called while CCCR.CCE = 1.
+    #[inline]
+    fn write_global_filter(&mut self, filter: GlobalFilter) {
+        self.can.gfc().modify(|w| {
+            w.set_anfs(filter.handle_standard_frames as u8);
+            w.set_anfe(filter.handle_extended_frames as u8);
+            w.set_rrfs(filter.reject_remote_standard_frames);
+            w.set_rrfe(filter.reject_remote_extended_frames);
+        });
+
+        self.config.global_filter = filter;
+    }
+}
+
+/// First setting found to differ between [FdCanConfig] and the registers, see [FdCan::verify_applied].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConfigMismatch {
+    NominalBitTiming,
+    DataBitTiming,
+    AutomaticRetransmit,
+    TransmitPause,
+    FrameTransmit,
+    NonIsoMode,
+    EdgeFiltering,
+    ProtocolExceptionHandling,
+    #[cfg(feature = "h7")]
+    WideMessageMarker,
+    InterruptEnable,
+    InterruptLineConfig,
+    InterruptLinesEnabled,
+    GlobalFilter,
+    TimestampSource,
+    #[cfg(feature = "h7")]
+    Layout,
+}
+
+/// Error codes of PSR.LEC (arbitration phase) and PSR.DLEC (data phase), both fields are reset to
+/// `NO_CHANGE` when PSR is read.
+mod lec {
+    pub(super) const NO_ERROR: u8 = 0b000;
+    pub(super) const FORM_ERROR: u8 = 0b010;
+    pub(super) const CRC_ERROR: u8 = 0b110;
+    pub(super) const NO_CHANGE: u8 = 0b111;
+}
+
+/// PSR with no events to report: LEC and DLEC at no change, PXE clear.
+pub(crate) const PSR_NO_EVENTS: Psr = Psr(((lec::NO_CHANGE as u32) << 8) | lec::NO_CHANGE as u32);
+
+impl<M> FdCan<M> {
+    /// Reads PSR for fields other than the events, e.g. ACT. LEC, DLEC and PXE reset by this read are kept for
+    /// the next [take_psr_events](Self::take_psr_events).
+    fn read_psr_status(&mut self) -> Psr {
+        let psr = self.can.psr().read();
+        self.psr_events = merge_psr_events(self.psr_events, psr);
+        psr
+    }
+
+    /// Reads PSR for its LEC, DLEC and PXE fields, including events an earlier
+    /// [read_psr_status](Self::read_psr_status) reset in hardware.
+    fn take_psr_events(&mut self) -> Psr {
+        let psr = merge_psr_events(self.psr_events, self.can.psr().read());
+        self.psr_events = PSR_NO_EVENTS;
+        psr
+    }
+}
+
+/// Keeps the events of `older` that `newer` does not replace: a fresh error code wins over a saved one, PXE is
+/// sticky.
+fn merge_psr_events(older: Psr, mut newer: Psr) -> Psr {
+    if newer.lec() == lec::NO_CHANGE {
+        newer.set_lec(older.lec());
+    }
+    if newer.dlec() == lec::NO_CHANGE {
+        newer.set_dlec(older.dlec());
+    }
+    newer.set_pxe(newer.pxe() || older.pxe());
+    newer
+}
+
+impl<M> FdCan<M> {
+    /// Frame transmission setting this instance was configured with, e.g. to decide between FD and classic
+    /// [TxFrameHeader](crate::TxFrameHeader)s.
+    ///
+    /// This is the stored setting, not a register read: [verify_applied](Self::verify_applied) reports
+    /// [ConfigMismatch::FrameTransmit] if CCCR.FDOE/BRSE do not match it.
+    #[inline]
+    pub fn frame_transmission_config(&self) -> FrameTransmissionConfig {
+        self.config.frame_transmit
+    }
+
+    /// Reads back every register [apply_config](FdCan::apply_config) writes and compares it to the configuration
+    /// this instance believes is in effect, returning the first setting that does not match.
+    ///
+    /// Most of these registers are only writable while CCCR.CCE = 1, a setter called at the wrong time is
+    /// silently ignored by the hardware. Run this after leaving config mode to catch that. The clock divider is
+    /// not checked: it is shared by all instances and not written by this driver.
+    pub fn verify_applied(&self) -> Result<(), ConfigMismatch> {
+        let config = &self.config;
+        let check = |ok: bool, mismatch: ConfigMismatch| if ok { Ok(()) } else { Err(mismatch) };
+        check(
+            self.can.nbtp().read() == config.nbtr.register_value(),
+            ConfigMismatch::NominalBitTiming,
+        )?;
+        check(
+            self.can.dbtp().read() == config.dbtr.register_value(),
+            ConfigMismatch::DataBitTiming,
+        )?;
+        let cccr = self.can.cccr().read();
+        check(
+            cccr.dar() != config.automatic_retransmit,
+            ConfigMismatch::AutomaticRetransmit,
+        )?;
+        check(
+            cccr.txp() == config.transmit_pause,
+            ConfigMismatch::TransmitPause,
+        )?;
+        let (fdoe, brse) = config.frame_transmit.cccr_bits();
+        check(
+            cccr.fdoe() == fdoe && cccr.bse() == brse,
+            ConfigMismatch::FrameTransmit,
+        )?;
+        check(
+            cccr.niso() == config.non_iso_mode,
+            ConfigMismatch::NonIsoMode,
+        )?;
+        check(
+            cccr.efbi() == config.edge_filtering,
+            ConfigMismatch::EdgeFiltering,
+        )?;
+        check(
+            cccr.pxhd() != config.protocol_exception_handling,
+            ConfigMismatch::ProtocolExceptionHandling,
+        )?;
+        #[cfg(feature = "h7")]
+        check(
+            cccr.wmm() == config.wide_message_marker,
+            ConfigMismatch::WideMessageMarker,
+        )?;
+        check(
+            self.can.ie().read().0 == config.interrupt_enable.0,
+            ConfigMismatch::InterruptEnable,
+        )?;
+        check(
+            self.can.ils().read().0 == config.interrupt_line_config.0,
+            ConfigMismatch::InterruptLineConfig,
+        )?;
+        let ile = self.can.ile().read();
+        check(
+            (ile.eint0(), ile.eint1()) == config.interrupt_lines_enabled,
+            ConfigMismatch::InterruptLinesEnabled,
+        )?;
+        check(
+            self.global_filter() == config.global_filter,
+            ConfigMismatch::GlobalFilter,
+        )?;
+        let tscc = self.can.tscc().read();
+        let expected = config.timestamp_source.register_value();
+        check(
+            tscc.tss() == expected.tss() && tscc.tcp() == expected.tcp(),
+            ConfigMismatch::TimestampSource,
+        )?;
+        #[cfg(feature = "h7")]
+        check(self.layout_applied(), ConfigMismatch::Layout)?;
+        Ok(())
+    }
+
+    /// Compares every layout register written by [set_layout](FdCan::set_layout) with the configured layout.
+    #[cfg(feature = "h7")]
+    fn layout_applied(&self) -> bool {
+        let l = &self.config.layout;
+        let sidfc = self.can.sidfc().read();
+        let xidfc = self.can.xidfc().read();
+        let rxf0c = self.can.rxfc(0).read();
+        let rxf1c = self.can.rxfc(1).read();
+        let rxesc = self.can.rxesc().read();
+        let txefc = self.can.txefc().read();
+        let txbc = self.can.txbc().read();
+        let tttmc = self.can.tttmc().read();
+        sidfc.flssa() == l.eleven_bit_filters_addr
+            && sidfc.lss() == l.eleven_bit_filters_len
+            && xidfc.flesa() == l.twenty_nine_bit_filters_addr
+            && xidfc.lse() == l.twenty_nine_bit_filters_len
+            && rxf0c.fsa() == l.rx_fifo0_addr
+            && rxf0c.fs() == l.rx_fifo0_len
+            && rxf1c.fsa() == l.rx_fifo1_addr
+            && rxf1c.fs() == l.rx_fifo1_len
+            && self.can.rxbc().read().rbsa() == l.rx_buffers_addr
+            && rxesc.rbds() == l.rx_buffers_data_size.config_register()
+            && rxesc.fds(0) == l.rx_fifo0_data_size.config_register()
+            && rxesc.fds(1) == l.rx_fifo1_data_size.config_register()
+            && txefc.efsa() == l.tx_event_fifo_addr
+            && txefc.efs() == l.tx_event_fifo_len
+            && txbc.tbsa() == l.tx_buffers_addr
+            && txbc.tfqs() == l.tx_fifo_or_queue_len
+            && txbc.ndtb() == l.tx_buffers_len
+            && self.can.txesc().read().tbds() == l.tx_buffers_data_size.config_register()
+            && tttmc.tmsa() == l.trigger_memory_addr
+            && tttmc.tme() == l.trigger_memory_len
+    }
+
+    /// Reads back global filter settings from the GFC register, useful to verify what is actually in effect.
+    #[inline]
+    pub fn global_filter(&self) -> GlobalFilter {
+        let gfc = self.can.gfc().read();
+        GlobalFilter {
+            handle_standard_frames: NonMatchingFilter::from_bits(gfc.anfs()),
+            handle_extended_frames: NonMatchingFilter::from_bits(gfc.anfe()),
+            reject_remote_standard_frames: gfc.rrfs(),
+            reject_remote_extended_frames: gfc.rrfe(),
+        }
+    }
+
+    /// Returns `true` if non-ISO (Bosch) CAN FD operation is enabled in CCCR.NISO.
+    ///
+    /// All nodes on a bus must agree on this setting, otherwise every FD frame fails with a CRC or form error.
+    #[inline]
+    pub fn non_iso_mode(&self) -> bool {
+        self.can.cccr().read().niso()
+    }
+
+    /// Reads the transmit and receive error counters (ECR). Reading ECR resets CEL.
+    #[inline]
+    pub fn error_counters(&self) -> ErrorCounters {
+        let ecr = self.can.ecr().read();
+        ErrorCounters {
+            transmit: ecr.tec(),
+            receive: ecr.rec(),
+            receive_passive: ecr.rp(),
+            error_logging: ecr.cel(),
+        }
+    }
+
+    /// Current message RAM watchdog counter (RWD.WDV), see [set_ram_watchdog](FdCan::set_ram_watchdog).
+    ///
+    /// Reads the start value while no message RAM access is pending. A value stuck below it, or 0, while the bus
+    /// is busy points at a message RAM clock problem.
+    #[inline]
+    pub fn ram_watchdog_value(&self) -> u8 {
+        self.can.rwd().read().wdv()
+    }
+
+    /// Returns `true` if the message RAM watchdog expired (IR.WDI), the flag is left set.
+    #[cfg(feature = "h7")]
+    #[inline]
+    pub fn ram_watchdog_expired(&self) -> bool {
+        self.can.ir().read().wdi()
+    }
+
+    /// Raw CCCR register contents, including the NISO bit, for diagnostics.
+    #[inline]
+    pub fn cccr_snapshot(&self) -> Cccr {
+        self.can.cccr().read()
+    }
+
+    /// Hint that this node and the bus disagree on ISO vs non-ISO CAN FD, see [Self::non_iso_mode].
+    ///
+    /// Returns `true` if the last error in the data phase of an FD frame was a CRC or form error.
+    /// A single error is not conclusive, call this repeatedly and only suspect a mismatch if it keeps
+    /// returning `true` while classic frames are received fine.
+    /// Reading PSR resets its last error code fields, so this races with other users of PSR outside this driver.
+    #[inline]
+    pub fn possible_iso_mismatch(&mut self) -> bool {
+        let dlec = self.take_psr_events().dlec();
+        dlec == lec::FORM_ERROR || dlec == lec::CRC_ERROR
+    }
+
+    /// Returns `true` if protocol exception handling is enabled (CCCR.PXHD = 0).
+    #[inline]
+    pub fn protocol_exception_handling(&self) -> bool {
+        !self.can.cccr().read().pxhd()
+    }
+
+    /// Returns `true` if a protocol exception event occurred since the last read of PSR: a recessive res bit
+    /// was sampled and the node went to bus integrating state.
+    ///
+    /// Reading PSR resets this flag and the last error code fields.
+    #[inline]
+    pub fn protocol_exception_event(&mut self) -> bool {
+        self.take_psr_events().pxe()
+    }
+
+    /// Hint that frames in a format this core does not support, i.e. CAN XL, are present on the bus.
+    ///
+    /// A CAN XL frame looks like an FD frame with the res bit (XLF in CAN XL) sampled recessive. What this core
+    /// reports for it depends on [protocol exception handling](Self::protocol_exception_handling):
+    /// * enabled (default): no error frame is sent, PSR.PXE is set and the node goes to bus integrating state
+    ///   until 11 recessive bits are seen, LEC is not updated.
+    /// * disabled: the recessive res bit is a form error in arbitration phase, PSR.LEC reads 2 (form error) and
+    ///   an error frame destroys the CAN XL frame, so recurring form errors on an otherwise healthy bus are
+    ///   the symptom.
+    ///
+    /// Returns `true` if the last read of PSR matches the pattern for the current setting. Like
+    /// [possible_iso_mismatch](Self::possible_iso_mismatch), a single hit is not conclusive and reading PSR
+    /// resets PXE and the last error code fields.
+    pub fn likely_unsupported_frame_format(&mut self) -> bool {
+        let exception_handling = self.protocol_exception_handling();
+        let psr = self.take_psr_events();
+        if exception_handling {
+            psr.pxe()
+        } else {
+            psr.lec() == lec::FORM_ERROR
+        }
+    }
+
+    /// Protocol error in arbitration phase (IR.PEA) and in data phase (IR.PED) interrupt flags.
+    ///
+    /// When the `asynchronous` feature is enabled, flags are cleared in the interrupt handler, use
+    /// `wait_protocol_error` (`embassy` feature) instead.
+    #[inline]
+    pub fn protocol_error_flags(&self) -> (bool, bool) {
+        let ir = self.can.ir().read();
+        (ir.pea(), ir.ped())
+    }
+
+    /// Clears IR.PEA and IR.PED flags.
+    #[inline]
+    pub fn clear_protocol_error_flags(&mut self) {
+        self.can.ir().write(|w| {
+            w.set_pea(true);
+            w.set_ped(true);
+        });
+    }
+
+    /// Clears the interrupt flags set in `flags`, others are left untouched (IR is write 1 to clear). Use
+    /// [InterruptMask] to build `flags`.
+    ///
+    /// When the `asynchronous` feature is enabled, the interrupt handler clears every flag it services, clearing
+    /// them here as well can make it miss events.
+    #[inline]
+    pub fn clear_interrupt_flags(&mut self, flags: Ir) {
+        self.can
+            .ir()
+            .write_value(Ir(flags.0 & InterruptMask::ALL.bits()));
+    }
+
+    /// Clears all interrupt flags, e.g. before enabling interrupts or at the end of a polling iteration.
+    #[inline]
+    pub fn clear_all_interrupt_flags(&mut self) {
+        self.clear_interrupt_flags(Ir(InterruptMask::ALL.bits()));
+    }
+}
+
+impl FdCan<BusMonitoringMode> {
+    /// Tries each of the `candidates` nominal bit timings in turn and returns the first one with which at least
+    /// one valid frame was received into RX FIFO0 or FIFO1 without any protocol errors being detected.
+    ///
+    /// Each candidate is listened to for up to `window_iterations` polling iterations, this window must cover
+    /// bus integration (11 recessive bits) and at least one frame on the bus. Bus monitoring mode never drives
+    /// the bus (no ACK, no error frames), so scanning does not disturb other nodes. Global filter or filters must
+    /// accept the frames into one of the FIFOs for them to be detected.
+    ///
+    /// If no candidate matched, original nominal bit timing is restored and `None` is returned. If switching to a
+    /// candidate fails, the original timing is restored as well before the error is returned. Only if that fails
+    /// too, the timing and CCCR.INIT are left as the failed attempt left them.
+    pub fn detect_bitrate(
+        &mut self,
+        candidates: &[NominalBitTiming],
+        window_iterations: u32,
+    ) -> Result<Option<NominalBitTiming>, Error> {
+        let original = self.config.nbtr;
+        let dbtr = self.config.dbtr;
+        for candidate in candidates {
+            if let Err(e) = self.reconfigure_timing(*candidate, dbtr) {
+                let _ = self.reconfigure_timing(original, dbtr);
+                return Err(e);
+            }
+            if self.frame_received_without_errors(window_iterations) {
+                return Ok(Some(*candidate));
+            }
+        }
+        self.reconfigure_timing(original, dbtr)?;
+        Ok(None)
+    }
+
+    fn frame_received_without_errors(&mut self, window_iterations: u32) -> bool {
+        // LEC and DLEC are set to no change on read, discard anything left from the previous candidate.
+        _ = self.take_psr_events();
+        for _ in 0..window_iterations {
+            let psr = self.take_psr_events();
+            // Anything but no error or no change since the last read is a protocol error
+            let is_error = |code: u8| code != lec::NO_ERROR && code != lec::NO_CHANGE;
+            if is_error(psr.lec()) || is_error(psr.dlec()) {
+                return false;
+            }
+            if self.can.rxfs(0).read().ffl() > 0 || self.can.rxfs(1).read().ffl() > 0 {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Snapshot of the error counter register (ECR), see [FdCan::error_counters]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ErrorCounters {
+    /// Transmit error counter (TEC)
+    pub transmit: u8,
+    /// Receive error counter (REC), 127 at most, see `receive_passive` above that
+    pub receive: u8,
+    /// Receive error counter reached the error passive level of 128 (RP)
+    pub receive_passive: bool,
+    /// CAN error logging (CEL), errors counted since the last read, saturating at 255
+    pub error_logging: u8,
+}
+
+/// Source of the level on the FDCAN TX pin in TestMode (TEST.TX)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TxPinControl {
+    /// TX pin is driven by the CAN core, updated at the end of the bit time
+    Core = 0b00,
+    /// Sample point is monitored on the TX pin
+    SamplePoint = 0b01,
+    /// TX pin is held dominant (0)
+    Dominant = 0b10,
+    /// TX pin is held recessive (1)
+    Recessive = 0b11,
+}
+
+impl FdCan<TestMode> {
+    /// Takes the TX pin away from the CAN core, or hands it back with [TxPinControl::Core].
+    ///
+    /// Holding the pin dominant in the middle of another node's frame forces bit, stuff, CRC or form errors on
+    /// the bus, depending on where it lands. Use it on test rigs only, it destroys ongoing traffic.
+    #[inline]
+    pub fn set_tx_pin(&mut self, control: TxPinControl) {
+        self.can.test().modify(|w| w.set_tx(control as u8));
+    }
+
+    /// Drives the TX pin to `control` for `cpu_cycles` and then hands it back to the CAN core.
+    ///
+    /// The duration is a busy wait counted in CPU clock cycles, not FDCAN kernel clock cycles, and it is only as
+    /// precise as interrupts allow; mask them around the call to hit a given bit position. One bit time is
+    /// `cpu_hz / bitrate` CPU cycles, e.g. 400 cycles at 400 MHz and 1 Mbit/s, with the bitrate taken from
+    /// [NominalBitTiming::bitrate] for the kernel clock. The CPU and kernel clocks usually differ, so a bit time
+    /// in time quanta has to be scaled by `cpu_hz / kernel_hz` as well.
+    pub fn hold_tx_pin(&mut self, control: TxPinControl, cpu_cycles: u32) {
+        self.set_tx_pin(control);
+        cortex_m::asm::delay(cpu_cycles);
+        self.set_tx_pin(TxPinControl::Core);
+    }
+
+    /// Current level of the RX pin (TEST.RX), `true` is recessive.
+    #[inline]
+    pub fn rx_pin(&self) -> bool {
+        self.can.test().read().rx()
+    }
+
+    /// Enables or disables loopback (TEST.LBCK): the core receives its own frames and ignores ACK errors,
+    /// while the TX pin still follows [set_tx_pin](Self::set_tx_pin).
+    #[inline]
+    pub fn set_loopback(&mut self, enabled: bool) {
+        self.can.test().modify(|w| w.set_lbck(enabled));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculated_timings_yield_the_requested_bitrate() {
+        let nominal = NominalBitTiming::from_bitrate(80_000_000, 500_000).unwrap();
+        assert_eq!(nominal.bitrate(80_000_000), 500_000);
+        assert_eq!(nominal.sample_point_permille(), 875);
+        let data = DataBitTiming::from_bitrate(80_000_000, 2_000_000).unwrap();
+        assert_eq!(data.bitrate(80_000_000), 2_000_000);
+        assert_eq!(data.sample_point_permille(), 800);
+    }
+
+    #[cfg(feature = "h7")]
+    #[test]
+    fn error_counters_decode_ecr() {
+        use crate::FdCanInstance;
+        use crate::fdcan::tests::host_fdcan;
+        use crate::message_ram_layout::tests::host_ram;
+        use crate::pac::registers::regs::Ecr;
+
+        let can = host_fdcan::<NormalOperationMode>(FdCanInstance::FdCan1, host_ram());
+        // CEL[23:16], RP[15], REC[14:8], TEC[7:0]
+        can.can.ecr().write_value(Ecr(0x00A5_FF12));
+        assert_eq!(
+            can.error_counters(),
+            ErrorCounters {
+                transmit: 0x12,
+                receive: 0x7F,
+                receive_passive: true,
+                error_logging: 0xA5,
+            }
+        );
+    }
+
+    #[cfg(feature = "h7")]
+    #[test]
+    fn psr_error_codes_drive_the_diagnostic_hints() {
+        use crate::FdCanInstance;
+        use crate::fdcan::tests::host_fdcan;
+        use crate::message_ram_layout::tests::host_ram;
+
+        let mut can = host_fdcan::<NormalOperationMode>(FdCanInstance::FdCan1, host_ram());
+        let mut psr = Psr(0);
+        psr.set_lec(lec::NO_CHANGE);
+        psr.set_dlec(lec::CRC_ERROR);
+        can.can.psr().write_value(psr);
+        assert!(can.possible_iso_mismatch());
+        psr.set_dlec(lec::NO_CHANGE);
+        can.can.psr().write_value(psr);
+        assert!(!can.possible_iso_mismatch());
+
+        // Protocol exception handling disabled: a CAN XL frame shows up as a form error
+        can.can.cccr().modify(|w| w.set_pxhd(true));
+        psr.set_lec(lec::FORM_ERROR);
+        can.can.psr().write_value(psr);
+        assert!(can.likely_unsupported_frame_format());
+    }
+
+    #[cfg(feature = "h7")]
+    #[test]
+    fn events_reset_by_bus_integration_polls_are_kept() {
+        use crate::FdCanInstance;
+        use crate::fdcan::tests::host_fdcan;
+        use crate::message_ram_layout::tests::host_ram;
+
+        const ERROR_ACTIVE: u8 = 0b01;
+        let mut can = host_fdcan::<NormalOperationMode>(FdCanInstance::FdCan1, host_ram());
+        can.can.cccr().modify(|w| w.set_pxhd(true));
+        let mut psr = PSR_NO_EVENTS;
+        psr.set_act(ERROR_ACTIVE);
+        psr.set_lec(lec::FORM_ERROR);
+        can.can.psr().write_value(psr);
+        assert!(can.is_bus_integrated());
+
+        // What the core does on that read
+        psr.set_lec(lec::NO_CHANGE);
+        can.can.psr().write_value(psr);
+        assert!(can.likely_unsupported_frame_format());
+        assert!(!can.likely_unsupported_frame_format());
     }
 }
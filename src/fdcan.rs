@@ -1,18 +1,36 @@
-use crate::config::FdCanConfig;
+use crate::config::{FdCanConfig, PSR_NO_EVENTS};
+use crate::message_ram_layout::MessageRamBase;
+use crate::pac::registers::regs::Psr;
 use crate::pac::{
-    FDCAN_MSGRAM_ADDR, FDCAN_MSGRAM_LEN_WORDS, FDCAN1_REGISTER_BLOCK_ADDR,
-    FDCAN2_REGISTER_BLOCK_ADDR, RCC_REGISTER_BLOCK_ADDR,
+    FDCAN_MSGRAM_LEN_WORDS, FDCAN1_REGISTER_BLOCK_ADDR, FDCAN2_REGISTER_BLOCK_ADDR,
+    RCC_REGISTER_BLOCK_ADDR,
 };
 use crate::{CLOCK_DOMAIN_SYNCHRONIZATION_DELAY, pac};
 use core::marker::PhantomData;
 use static_cell::StaticCell;
 
+#[must_use = "dropping an FdCan leaves the instance running on the bus, see the stop-on-drop feature"]
 pub struct FdCan<M> {
     pub(crate) can: pac::registers::Fdcan,
     pub(crate) instance: FdCanInstance,
     #[cfg(feature = "embassy")]
     pub(crate) state: &'static crate::asynchronous::State,
     pub(crate) config: FdCanConfig,
+    /// Message RAM shared by all instances
+    pub(crate) msg_ram: MessageRamBase,
+    /// TXBTO bits already reported by [on_tx_complete](FdCan::on_tx_complete)
+    pub(crate) tx_completed_seen: u32,
+    /// Dedicated TX buffers written by [write_tx_buffer](FdCan::write_tx_buffer) but not requested yet
+    pub(crate) tx_staged: u32,
+    #[cfg(feature = "stats")]
+    pub(crate) rx_stats: crate::tx_rx::RxStats,
+    /// An FD frame was received, see [fd_seen](FdCan::fd_seen)
+    pub(crate) fd_rx_seen: bool,
+    /// A Tx event for an FD frame was read, see [fd_tx_completed](FdCan::fd_tx_completed)
+    pub(crate) fd_tx_completed: bool,
+    /// LEC, DLEC and PXE reset by a PSR read the driver did for other fields, see
+    /// [is_bus_integrated](FdCan::is_bus_integrated)
+    pub(crate) psr_events: Psr,
     pub(crate) _mode: PhantomData<M>,
 }
 
@@ -22,6 +40,54 @@ pub trait Transmit {}
 /// Allows for Receive Operations
 pub trait Receive {}
 
+/// Allows for runtime reconfiguration of an instance that already left ConfigMode
+pub trait Operational {}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Implemented by every mode marker, allows generic code holding an `FdCan<M>` to tell which mode it is in.
+pub trait Mode: sealed::Sealed {
+    const MODE_NAME: &'static str;
+}
+
+macro_rules! impl_mode {
+    ($($mode:ident),*) => {
+        $(
+            impl sealed::Sealed for $mode {}
+            impl Mode for $mode {
+                const MODE_NAME: &'static str = stringify!($mode);
+            }
+        )*
+    };
+}
+
+impl_mode!(
+    PoweredDownMode,
+    ConfigMode,
+    InternalLoopbackMode,
+    ExternalLoopbackMode,
+    NormalOperationMode,
+    RestrictedOperationMode,
+    BusMonitoringMode,
+    TestMode
+);
+
+impl<M: Mode> FdCan<M> {
+    /// Name of the current mode, for logging.
+    #[inline]
+    pub fn mode_name(&self) -> &'static str {
+        M::MODE_NAME
+    }
+
+    /// Peripheral this driver controls, e.g. to pass to `asynchronous::on_interrupt` from the matching ISR.
+    #[inline]
+    pub fn instance(&self) -> FdCanInstance {
+        self.instance
+    }
+}
+
 /// Allows for the FdCan Instance to enter ConfigMode or for it's clock to be disabled.
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct PoweredDownMode;
@@ -38,6 +104,7 @@ pub struct ConfigMode;
 pub struct InternalLoopbackMode;
 impl Transmit for InternalLoopbackMode {}
 impl Receive for InternalLoopbackMode {}
+impl Operational for InternalLoopbackMode {}
 
 /// This mode is provided for hardware self-test. To be independent of external stimulation,
 /// the FDCAN ignores acknowledge errors (recessive bit sampled in the acknowledgement slot of a
@@ -49,12 +116,14 @@ impl Receive for InternalLoopbackMode {}
 pub struct ExternalLoopbackMode;
 impl Transmit for ExternalLoopbackMode {}
 impl Receive for ExternalLoopbackMode {}
+impl Operational for ExternalLoopbackMode {}
 
 /// The normal use of the FdCan instance after configurations
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct NormalOperationMode;
 impl Transmit for NormalOperationMode {}
 impl Receive for NormalOperationMode {}
+impl Operational for NormalOperationMode {}
 
 /// In Restricted operation mode, the node is able to receive data and remote frames and to give
 /// acknowledgement to valid frames, but it does not send data frames, remote frames, active error
@@ -65,6 +134,7 @@ impl Receive for NormalOperationMode {}
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct RestrictedOperationMode;
 impl Receive for RestrictedOperationMode {}
+impl Operational for RestrictedOperationMode {}
 
 ///  In Bus monitoring mode (for more details refer to ISO11898-1, 10.12 Bus monitoring),
 /// the FDCAN is able to receive valid data frames and valid remote frames, but cannot start a
@@ -77,13 +147,16 @@ impl Receive for RestrictedOperationMode {}
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct BusMonitoringMode;
 impl Receive for BusMonitoringMode {}
+impl Operational for BusMonitoringMode {}
 
 /// Test mode must be used for production tests or self-test only. The software control for
 /// FDCAN_TX pin interferes with all CAN protocol functions. It is not recommended to use test
 /// modes for application.
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct TestMode;
+impl Operational for TestMode {}
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Error {
     PeripheralTaken,
@@ -97,9 +170,36 @@ pub enum Error {
     /// or tried to use TxBufferIdx from one CAN instance with another.
     WrongInstance,
     TxBufferIndexOutOfRange,
+    RxBufferIndexOutOfRange,
+    TxEventIndexOutOfRange,
+    /// Filter element index is outside of the filter list allocated in the RAM layout.
+    FilterIndexOutOfRange,
+    /// Trigger element index is outside of the trigger memory allocated in the RAM layout.
+    TriggerIndexOutOfRange,
+    /// The core is in restricted operation or bus monitoring mode and would never send the frame.
+    TransmitNotAllowed,
     WrongDataSize,
+    /// TX FIFO/Queue is full, try again after some frames were transmitted.
+    WouldBlock,
+    /// Transmission request finished without the frame being transmitted (cancelled or failed without retransmission).
+    TransmissionNotCompleted,
+    /// Requested bitrate cannot be reached exactly with the FDCAN kernel clock.
+    UnsupportedBitrate,
+    /// Instance is neither in initialization nor in clock stop state, clock cannot be disabled.
+    InstanceNotIdle,
+    /// Filter or TX buffer elements or the layout's RAM region were accessed before the RAM layout was applied with
+    /// [set_layout](FdCan::set_layout) or [apply_config](FdCan::apply_config).
+    LayoutNotApplied,
 }
 
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(self, f)
+    }
+}
+
+impl core::error::Error for Error {}
+
 pub(crate) enum LoopbackMode {
     None,
     Internal,
@@ -122,7 +222,7 @@ pub struct FdCanInstances {
 }
 
 /// FDCAN instance number as an enum
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum FdCanInstance {
     FdCan1,
@@ -131,11 +231,49 @@ pub enum FdCanInstance {
     FdCan3,
 }
 
+/// FDCAN interrupt line, FDCAN_INT0 or FDCAN_INT1 of an instance
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum FdCanInterrupt {
     Irq0,
     Irq1,
 }
 
+impl FdCanInstance {
+    /// Name of the NVIC interrupt vector `line` of this instance is wired to, as used by the device PAC and
+    /// `#[interrupt]` handlers.
+    ///
+    /// This crate owns the FDCAN registers but not the NVIC. For an interrupt to fire, the source has to be
+    /// enabled (IE), routed to a line ([select_interrupt_line_1](FdCan::select_interrupt_line_1)), the line enabled
+    /// ([enable_interrupt_lines](FdCan::enable_interrupt_lines)) and this vector unmasked in the NVIC, with a
+    /// handler of the same name calling `asynchronous::on_interrupt`:
+    /// ```ignore
+    /// unsafe { NVIC::unmask(pac::Interrupt::FDCAN1_IT0) };
+    ///
+    /// #[interrupt]
+    /// fn FDCAN1_IT0() {
+    ///     mcan::asynchronous::on_interrupt(FdCanInstance::FdCan1, FdCanInterrupt::Irq0);
+    /// }
+    /// ```
+    /// On G0 both instances share one vector per line, the handler has to service both of them.
+    pub const fn interrupt_vector(&self, line: FdCanInterrupt) -> &'static str {
+        #[cfg(feature = "h7")]
+        match (self, line) {
+            (FdCanInstance::FdCan1, FdCanInterrupt::Irq0) => "FDCAN1_IT0",
+            (FdCanInstance::FdCan1, FdCanInterrupt::Irq1) => "FDCAN1_IT1",
+            (FdCanInstance::FdCan2, FdCanInterrupt::Irq0) => "FDCAN2_IT0",
+            (FdCanInstance::FdCan2, FdCanInterrupt::Irq1) => "FDCAN2_IT1",
+            (FdCanInstance::FdCan3, FdCanInterrupt::Irq0) => "FDCAN3_IT0",
+            (FdCanInstance::FdCan3, FdCanInterrupt::Irq1) => "FDCAN3_IT1",
+        }
+        #[cfg(not(feature = "h7"))]
+        match line {
+            FdCanInterrupt::Irq0 => "TIM16_FDCAN_IT0",
+            FdCanInterrupt::Irq1 => "TIM17_FDCAN_IT1",
+        }
+    }
+}
+
 #[cfg(feature = "h7")]
 type NewResult = (
     FdCanInstances,
@@ -146,6 +284,7 @@ type NewResult = (
 #[cfg(not(feature = "h7"))]
 type NewResult = FdCanInstances;
 
+// StaticCell instead of an AtomicBool swap, as thumbv6m (G0) has no atomic read-modify-write instructions.
 static PERIPHERAL_TAKEN: StaticCell<()> = StaticCell::new();
 
 impl FdCanInstances {
@@ -156,6 +295,7 @@ impl FdCanInstances {
             return Err(Error::PeripheralTaken);
         }
 
+        // SAFETY: fixed register block addresses, PERIPHERAL_TAKEN guarantees a single owner of each.
         let fdcan1_regs = unsafe { pac::registers::Fdcan::from_ptr(FDCAN1_REGISTER_BLOCK_ADDR) };
         let fdcan2_regs = unsafe { pac::registers::Fdcan::from_ptr(FDCAN2_REGISTER_BLOCK_ADDR) };
         #[cfg(feature = "h7")]
@@ -187,6 +327,14 @@ impl FdCanInstances {
             #[cfg(feature = "embassy")]
             state: fdcan1_state,
             config: FdCanConfig::default(),
+            msg_ram: MessageRamBase::PERIPHERAL,
+            tx_completed_seen: 0,
+            tx_staged: 0,
+            #[cfg(feature = "stats")]
+            rx_stats: Default::default(),
+            fd_rx_seen: false,
+            fd_tx_completed: false,
+            psr_events: PSR_NO_EVENTS,
             _mode: PhantomData,
         };
         let fdcan2 = FdCan {
@@ -195,6 +343,14 @@ impl FdCanInstances {
             #[cfg(feature = "embassy")]
             state: fdcan2_state,
             config: FdCanConfig::default(),
+            msg_ram: MessageRamBase::PERIPHERAL,
+            tx_completed_seen: 0,
+            tx_staged: 0,
+            #[cfg(feature = "stats")]
+            rx_stats: Default::default(),
+            fd_rx_seen: false,
+            fd_tx_completed: false,
+            psr_events: PSR_NO_EVENTS,
             _mode: PhantomData,
         };
         #[cfg(feature = "h7")]
@@ -204,6 +360,14 @@ impl FdCanInstances {
             #[cfg(feature = "embassy")]
             state: fdcan3_state,
             config: FdCanConfig::default(),
+            msg_ram: MessageRamBase::PERIPHERAL,
+            tx_completed_seen: 0,
+            tx_staged: 0,
+            #[cfg(feature = "stats")]
+            rx_stats: Default::default(),
+            fd_rx_seen: false,
+            fd_tx_completed: false,
+            psr_events: PSR_NO_EVENTS,
             _mode: PhantomData,
         };
         s.fdcan1 = Some(fdcan1);
@@ -219,6 +383,50 @@ impl FdCanInstances {
         r
     }
 
+    /// Creates a handle for `instance` out of thin air, to recover an instance whose handle was leaked
+    /// (e.g. with [core::mem::forget]) and can no longer be [put back](Self::put_back).
+    ///
+    /// The returned handle has the default [FdCanConfig], RAM layout included, whatever state the hardware is
+    /// actually in. Bring it through [into_config_mode](FdCan::into_config_mode), which re-initializes the
+    /// instance, before using it.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that no other handle to the same instance is alive or will be used again,
+    /// otherwise two owners race on the same registers and message RAM.
+    pub unsafe fn steal_instance(instance: FdCanInstance) -> FdCan<PoweredDownMode> {
+        let addr = match instance {
+            FdCanInstance::FdCan1 => FDCAN1_REGISTER_BLOCK_ADDR,
+            FdCanInstance::FdCan2 => FDCAN2_REGISTER_BLOCK_ADDR,
+            #[cfg(feature = "h7")]
+            FdCanInstance::FdCan3 => pac::FDCAN3_REGISTER_BLOCK_ADDR,
+        };
+        #[cfg(feature = "embassy")]
+        let state = match instance {
+            FdCanInstance::FdCan1 => crate::asynchronous::state_fdcan1(),
+            FdCanInstance::FdCan2 => crate::asynchronous::state_fdcan2(),
+            #[cfg(feature = "h7")]
+            FdCanInstance::FdCan3 => crate::asynchronous::state_fdcan3(),
+        };
+        FdCan {
+            // SAFETY: fixed register block address, exclusive use is guaranteed by the caller.
+            can: unsafe { pac::registers::Fdcan::from_ptr(addr) },
+            instance,
+            #[cfg(feature = "embassy")]
+            state,
+            config: FdCanConfig::default(),
+            msg_ram: MessageRamBase::PERIPHERAL,
+            tx_completed_seen: 0,
+            tx_staged: 0,
+            #[cfg(feature = "stats")]
+            rx_stats: Default::default(),
+            fd_rx_seen: false,
+            fd_tx_completed: false,
+            psr_events: PSR_NO_EVENTS,
+            _mode: PhantomData,
+        }
+    }
+
     /// There is no need to keep FdCanInstances around if all instances were taken from it, but if clocks need to be disabled, then
     /// this method can be used to re-create it.
     pub fn empty() -> Self {
@@ -259,6 +467,10 @@ impl FdCanInstances {
     }
 
     /// Disable clock for all instances if they are all present, otherwise return MissingInstances error.
+    ///
+    /// Each instance must be in initialization or clock stop state (CCCR.INIT or CCCR.CSA set), otherwise
+    /// [Error::InstanceNotIdle] is returned and the clock is left running, so that a controller still taking part in
+    /// bus communication is not stopped mid-frame.
     pub fn disable(&mut self) -> Result<(), Error> {
         #[cfg(feature = "h7")]
         let all_present = self.fdcan1.is_some() && self.fdcan2.is_some() && self.fdcan3.is_some();
@@ -268,6 +480,25 @@ impl FdCanInstances {
             return Err(Error::MissingInstance);
         }
 
+        #[cfg(feature = "g0")]
+        let is_enabled = self.rcc.apbenr1().read().fdcanen();
+        #[cfg(feature = "h7")]
+        let is_enabled = self.rcc.apb1henr().read().fdcanen();
+        // Registers can only be read with the clock running, nothing to check otherwise
+        if is_enabled {
+            let instances = [
+                self.fdcan1.as_ref(),
+                self.fdcan2.as_ref(),
+                #[cfg(feature = "h7")]
+                self.fdcan3.as_ref(),
+            ];
+            for fdcan in instances.into_iter().flatten() {
+                if !fdcan.is_idle_for_clock_disable() {
+                    return Err(Error::InstanceNotIdle);
+                }
+            }
+        }
+
         #[cfg(feature = "h7")]
         self.rcc.apb1henr().modify(|w| w.set_fdcanen(false));
         #[cfg(feature = "g0")]
@@ -405,35 +636,53 @@ impl<M> FdCan<M> {
         Ok(())
     }
 
+    /// Returns `true` if the instance is not participating in bus communication (CCCR.INIT or CCCR.CSA set).
+    #[inline]
+    pub(crate) fn is_idle_for_clock_disable(&self) -> bool {
+        let cccr = self.can.cccr().read();
+        cccr.init() || cccr.csa()
+    }
+
     // TODO: make async version that can await for power down mode
     #[inline]
-    pub(crate) fn set_power_down_mode(&mut self, enabled: bool) -> Result<(), Error> {
+    pub(crate) fn set_power_down_mode(
+        &mut self,
+        enabled: bool,
+        timeout_iterations: u32,
+    ) -> Result<(), Error> {
         // Clock stop requested. When clock stop is requested, first INIT and then CSA will be set after
         // all pending transfer requests have been completed and the CAN bus reached idle.
         self.can.cccr().modify(|w| w.set_csr(enabled));
         crate::util::checked_wait(
             || self.can.cccr().read().csa() != enabled,
-            self.config.timeout_iterations_long,
+            timeout_iterations,
         )?;
         Ok(())
     }
 
     #[inline]
-    fn enter_init_mode(&mut self) -> Result<(), Error> {
+    pub(crate) fn enter_init_mode(&mut self, timeout_iterations: u32) -> Result<(), Error> {
         // Due to the synchronization mechanism between the two clock domains, there may be a
         // delay until the value written to INIT can be read back. Therefore, the programmer has to
         // ensure that the previous value written to INIT has been accepted by reading INIT before
         // setting INIT to a new value.
         self.can.cccr().modify(|w| w.set_init(true));
-        crate::util::checked_wait(
-            || !self.can.cccr().read().init(),
-            self.config.timeout_iterations_short,
-        )?;
+        cortex_m::asm::delay(CLOCK_DOMAIN_SYNCHRONIZATION_DELAY);
+        crate::util::checked_wait(|| !self.can.cccr().read().init(), timeout_iterations)?;
         // 1 = The CPU has write access to the protected configuration registers (while CCCR.INIT = ‘1’)
         self.can.cccr().modify(|w| w.set_cce(true));
         Ok(())
     }
 
+    #[inline]
+    pub(crate) fn exit_init_mode(&mut self, timeout_iterations: u32) -> Result<(), Error> {
+        self.can.cccr().modify(|w| w.set_cce(false));
+        self.can.cccr().modify(|w| w.set_init(false));
+        cortex_m::asm::delay(CLOCK_DOMAIN_SYNCHRONIZATION_DELAY);
+        crate::util::checked_wait(|| self.can.cccr().read().init(), timeout_iterations)?;
+        Ok(())
+    }
+
     #[inline]
     fn zero_msg_ram(&mut self) {
         // In case the Message RAM is equipped with parity or ECC functionality, it is recommended
@@ -442,10 +691,9 @@ impl<M> FdCan<M> {
         // uninitialized Message RAM sections will activate interrupt IR.BEC (Bit Error Corrected)
         // or IR.BEU (Bit Error Uncorrected)
         for i in 0..FDCAN_MSGRAM_LEN_WORDS {
-            unsafe {
-                let ptr = FDCAN_MSGRAM_ADDR.add(i);
-                core::ptr::write_volatile(ptr, 0x0000_0000);
-            }
+            let ptr = self.msg_ram.word_ptr(i);
+            // SAFETY: `i` is below the message RAM length, the peripheral is in config mode and not accessing it.
+            unsafe { core::ptr::write_volatile(ptr, 0x0000_0000) };
         }
     }
 
@@ -488,24 +736,84 @@ impl<M> FdCan<M> {
     }
 
     pub(crate) fn into_mode<M2>(self) -> FdCan<M2> {
+        // The instance lives on in the new mode, it must not be stopped by `Drop` (stop-on-drop feature)
+        let this = core::mem::ManuallyDrop::new(self);
         FdCan {
-            can: self.can,
-            instance: self.instance,
+            can: this.can,
+            instance: this.instance,
             #[cfg(feature = "embassy")]
-            state: self.state,
-            config: self.config,
+            state: this.state,
+            config: this.config,
+            msg_ram: this.msg_ram,
+            tx_completed_seen: this.tx_completed_seen,
+            tx_staged: this.tx_staged,
+            #[cfg(feature = "stats")]
+            rx_stats: this.rx_stats,
+            fd_rx_seen: this.fd_rx_seen,
+            fd_tx_completed: this.fd_tx_completed,
+            psr_events: this.psr_events,
             _mode: Default::default(),
         }
     }
 }
 
+/// With the `stop-on-drop` feature, an instance dropped while participating in bus communication is put into
+/// initialization (CCCR.INIT), so that it stops sending frames, ACKs and error frames. Its clock keeps running,
+/// use [into_powered_down](FdCan::into_powered_down) and [FdCanInstances::put_back] for a full teardown.
+///
+/// Off by default: embedded applications often leak instances on purpose, e.g. to hand them to a task forever.
+#[cfg(feature = "stop-on-drop")]
+impl<M> Drop for FdCan<M> {
+    fn drop(&mut self) {
+        // Registers read as 0 while the kernel clock is gated, there is nothing to stop then.
+        if self.can.endn().read().0 != 0x87654321_u32 || self.can.cccr().read().init() {
+            return;
+        }
+        self.can.cccr().modify(|w| w.set_init(true));
+        #[cfg(all(feature = "defmt", debug_assertions))]
+        defmt::warn!(
+            "{} dropped while operational, bus participation stopped",
+            self.instance
+        );
+    }
+}
+
 impl FdCan<PoweredDownMode> {
+    /// Returns `true` if clock stop was acknowledged (CCCR.CSA), i.e. this instance is parked and can have its
+    /// clocks switched off.
+    ///
+    /// Clock stop is per instance: other instances keep running while this one sits in PoweredDownMode. The
+    /// kernel and bus clock in RCC are shared though, gating them is only possible through
+    /// [FdCanInstances::disable] once every instance is powered down.
+    #[inline]
+    pub fn is_clock_stopped(&self) -> bool {
+        self.can.cccr().read().csa()
+    }
+
     /// Enable peripheral clock, reset and enable configuration mode
     #[inline]
+    #[allow(clippy::result_large_err)]
     pub fn into_config_mode(
         mut self,
     ) -> Result<FdCan<ConfigMode>, (Error, FdCan<PoweredDownMode>)> {
-        if let Err(e) = self.try_config_mode() {
+        if let Err(e) = self.try_config_mode(true) {
+            return Err((e, self));
+        }
+
+        Ok(self.into_mode())
+    }
+
+    /// Same as [into_config_mode](Self::into_config_mode), but leaves message RAM untouched, so filters and
+    /// buffers programmed before powering down are kept across a runtime reconfiguration.
+    ///
+    /// Only use it for re-entering config mode: message RAM must have been zeroed at least once after reset,
+    /// otherwise reading uninitialized words may raise parity/ECC errors (IR.BEC/IR.BEU).
+    #[inline]
+    #[allow(clippy::result_large_err)]
+    pub fn into_config_mode_preserving_ram(
+        mut self,
+    ) -> Result<FdCan<ConfigMode>, (Error, FdCan<PoweredDownMode>)> {
+        if let Err(e) = self.try_config_mode(false) {
             return Err((e, self));
         }
 
@@ -513,11 +821,13 @@ impl FdCan<PoweredDownMode> {
     }
 
     #[inline]
-    fn try_config_mode(&mut self) -> Result<(), Error> {
+    fn try_config_mode(&mut self, zero_ram: bool) -> Result<(), Error> {
         self.check_core()?;
-        self.set_power_down_mode(false)?;
-        self.enter_init_mode()?;
-        self.zero_msg_ram();
+        self.set_power_down_mode(false, self.config.timeout_iterations_long)?;
+        self.enter_init_mode(self.config.timeout_iterations_short)?;
+        if zero_ram {
+            self.zero_msg_ram();
+        }
         Ok(())
     }
 }
@@ -528,3 +838,34 @@ impl<M> defmt::Format for FdCan<M> {
         defmt::write!(f, "FdCan<{}>", self.instance)
     }
 }
+
+#[cfg(all(test, feature = "h7"))]
+pub(crate) mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::boxed::Box;
+
+    /// Instance backed by a zeroed host buffer for its registers and by `msg_ram`, registers read back
+    /// whatever was written last and nothing happens on its own.
+    pub(crate) fn host_fdcan<M>(instance: FdCanInstance, msg_ram: MessageRamBase) -> FdCan<M> {
+        let regs = Box::leak(std::vec![0u32; 0x400 / 4].into_boxed_slice());
+        FdCan {
+            // SAFETY: the leaked buffer covers the whole register block and is only used by this instance.
+            can: unsafe { pac::registers::Fdcan::from_ptr(regs.as_mut_ptr() as *mut ()) },
+            instance,
+            #[cfg(feature = "embassy")]
+            state: crate::asynchronous::state_fdcan1(),
+            config: FdCanConfig::default(),
+            msg_ram,
+            tx_completed_seen: 0,
+            tx_staged: 0,
+            #[cfg(feature = "stats")]
+            rx_stats: Default::default(),
+            fd_rx_seen: false,
+            fd_tx_completed: false,
+            psr_events: PSR_NO_EVENTS,
+            _mode: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,401 @@
+//! Standard (11-bit) and extended (29-bit) acceptance filter elements.
+
+#[cfg(feature = "h7")]
+use crate::config::NonMatchingFilter;
+#[cfg(feature = "h7")]
+use crate::fdcan::{Error, FdCan};
+use crate::id::{ExtendedId, StandardId};
+#[cfg(feature = "h7")]
+use crate::message_ram_layout::{FIFONr, MessageRamLayout};
+use crate::pac::message_ram::{
+    ExtendedFilterConfiguration, ExtendedFilterType, StandardFilterConfiguration,
+    StandardFilterType,
+};
+#[cfg(feature = "h7")]
+use crate::pac::message_ram::{
+    ExtendedFilterElementF0, ExtendedFilterElementF1, StandardFilterElement,
+};
+
+/// Frames accepted by a filter would be stored into RX FIFO or dedicated RX buffer that has no elements allocated
+/// in the RAM layout and silently lost, see [FdCan::validate_filters_against_layout].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConfigError {
+    /// Global filter routes non-matching 11-bit (`extended == false`) or 29-bit frames to an empty FIFO.
+    NonMatchingFramesToEmptyFifo { extended: bool },
+    /// 11-bit filter element at `index` stores into an empty FIFO or a missing dedicated RX buffer.
+    StandardFilterTargetMissing { index: u8 },
+    /// 29-bit filter element at `index` stores into an empty FIFO or a missing dedicated RX buffer.
+    ExtendedFilterTargetMissing { index: u8 },
+}
+
+/// 11-bit ID filter element.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StandardFilter {
+    pub filter_type: StandardFilterType,
+    pub config: StandardFilterConfiguration,
+    /// First ID, or the ID to match for classic filters.
+    pub id1: u16,
+    /// Second ID, upper bound for range filters or a mask for classic filters.
+    pub id2: u16,
+}
+
+impl StandardFilter {
+    /// Filter element that never matches.
+    pub const fn disabled() -> Self {
+        Self {
+            filter_type: StandardFilterType::Disabled,
+            config: StandardFilterConfiguration::Disable,
+            id1: 0,
+            id2: 0,
+        }
+    }
+
+    /// Matches frames where `received_id & mask == id & mask`.
+    pub const fn classic(id: StandardId, mask: u16, config: StandardFilterConfiguration) -> Self {
+        Self {
+            filter_type: StandardFilterType::Classic,
+            config,
+            id1: id.as_raw(),
+            id2: mask & 0x7FF,
+        }
+    }
+
+    /// Matches either of the two IDs.
+    pub const fn dual(
+        id1: StandardId,
+        id2: StandardId,
+        config: StandardFilterConfiguration,
+    ) -> Self {
+        Self {
+            filter_type: StandardFilterType::DualID,
+            config,
+            id1: id1.as_raw(),
+            id2: id2.as_raw(),
+        }
+    }
+
+    /// Matches IDs from `from` to `to` inclusive.
+    pub const fn range(
+        from: StandardId,
+        to: StandardId,
+        config: StandardFilterConfiguration,
+    ) -> Self {
+        Self {
+            filter_type: StandardFilterType::Range,
+            config,
+            id1: from.as_raw(),
+            id2: to.as_raw(),
+        }
+    }
+
+    #[cfg(feature = "h7")]
+    pub(crate) const fn to_element(self) -> StandardFilterElement {
+        StandardFilterElement::new()
+            .with_sft(self.filter_type)
+            .with_sfec(self.config)
+            .with_sfid1(self.id1)
+            .with_sfid2(self.id2)
+    }
+}
+
+/// 29-bit ID filter element.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ExtendedFilter {
+    pub filter_type: ExtendedFilterType,
+    pub config: ExtendedFilterConfiguration,
+    /// First ID, or the ID to match for classic filters.
+    pub id1: u32,
+    /// Second ID, upper bound for range filters or a mask for classic filters.
+    pub id2: u32,
+}
+
+impl ExtendedFilter {
+    /// Filter element that never matches.
+    pub const fn disabled() -> Self {
+        Self {
+            filter_type: ExtendedFilterType::Classic,
+            config: ExtendedFilterConfiguration::Disable,
+            id1: 0,
+            id2: 0,
+        }
+    }
+
+    /// Matches frames where `received_id & mask == id & mask`.
+    pub const fn classic(id: ExtendedId, mask: u32, config: ExtendedFilterConfiguration) -> Self {
+        Self {
+            filter_type: ExtendedFilterType::Classic,
+            config,
+            id1: id.as_raw(),
+            id2: mask & 0x1FFF_FFFF,
+        }
+    }
+
+    /// Matches either of the two IDs.
+    pub const fn dual(
+        id1: ExtendedId,
+        id2: ExtendedId,
+        config: ExtendedFilterConfiguration,
+    ) -> Self {
+        Self {
+            filter_type: ExtendedFilterType::DualID,
+            config,
+            id1: id1.as_raw(),
+            id2: id2.as_raw(),
+        }
+    }
+
+    /// Matches IDs from `from` to `to` inclusive, XIDAM mask is not applied.
+    pub const fn range(
+        from: ExtendedId,
+        to: ExtendedId,
+        config: ExtendedFilterConfiguration,
+    ) -> Self {
+        Self {
+            filter_type: ExtendedFilterType::RangeNoMask,
+            config,
+            id1: from.as_raw(),
+            id2: to.as_raw(),
+        }
+    }
+
+    #[cfg(feature = "h7")]
+    pub(crate) const fn to_elements(self) -> (ExtendedFilterElementF0, ExtendedFilterElementF1) {
+        (
+            ExtendedFilterElementF0::new()
+                .with_efec(self.config)
+                .with_efid1(self.id1),
+            ExtendedFilterElementF1::new()
+                .with_eft(self.filter_type)
+                .with_efid2(self.id2),
+        )
+    }
+}
+
+#[cfg(feature = "h7")]
+impl<M> FdCan<M> {
+    /// Writes a single 11-bit filter element, see [Self::configure_standard_filters].
+    #[inline]
+    pub fn configure_standard_filter(
+        &mut self,
+        idx: u8,
+        filter: StandardFilter,
+    ) -> Result<(), Error> {
+        self.configure_standard_filters(idx, &[filter])
+    }
+
+    /// Writes a contiguous run of 11-bit filter elements starting at `start_idx`.
+    ///
+    /// Bounds are checked once against the number of filters allocated in the RAM layout, nothing is written
+    /// if `start_idx + filters.len()` does not fit.
+    ///
+    /// The layout must be applied first with [set_layout](FdCan::set_layout) or
+    /// [apply_config](FdCan::apply_config), otherwise the filter list still starts at address 0 and
+    /// [Error::LayoutNotApplied] is returned.
+    pub fn configure_standard_filters(
+        &mut self,
+        start_idx: u8,
+        filters: &[StandardFilter],
+    ) -> Result<(), Error> {
+        if self.config.layout.is_empty() {
+            return Err(Error::LayoutNotApplied);
+        }
+        let elements = self
+            .message_ram()
+            .standard_filters(start_idx, filters.len())?;
+        for (element, filter) in elements.zip(filters) {
+            element.write_value(filter.to_element());
+        }
+        Ok(())
+    }
+
+    /// Writes a single 29-bit filter element, see [Self::configure_extended_filters].
+    #[inline]
+    pub fn configure_extended_filter(
+        &mut self,
+        idx: u8,
+        filter: ExtendedFilter,
+    ) -> Result<(), Error> {
+        self.configure_extended_filters(idx, &[filter])
+    }
+
+    /// Writes a contiguous run of 29-bit filter elements starting at `start_idx`.
+    ///
+    /// Bounds are checked once against the number of filters allocated in the RAM layout, nothing is written
+    /// if `start_idx + filters.len()` does not fit. Returns [Error::LayoutNotApplied] if the layout was not
+    /// applied yet, see [Self::configure_standard_filters].
+    pub fn configure_extended_filters(
+        &mut self,
+        start_idx: u8,
+        filters: &[ExtendedFilter],
+    ) -> Result<(), Error> {
+        if self.config.layout.is_empty() {
+            return Err(Error::LayoutNotApplied);
+        }
+        let elements = self
+            .message_ram()
+            .extended_filters(start_idx, filters.len())?;
+        for (element, filter) in elements.zip(filters) {
+            let (f0, f1) = filter.to_elements();
+            element.f0.write_value(f0);
+            element.f1.write_value(f1);
+        }
+        Ok(())
+    }
+
+    /// Cross-checks the global filter and every configured filter element against the applied RAM layout and
+    /// returns the first one that stores frames into an RX FIFO with no elements or into a dedicated RX buffer
+    /// that is not allocated. Call it once after filters are configured, it reads filter elements back from
+    /// message RAM.
+    pub fn validate_filters_against_layout(&self) -> Result<(), ConfigError> {
+        let layout = &self.config.layout;
+        let global = self.config.global_filter;
+        for (extended, handling) in [
+            (false, global.handle_standard_frames),
+            (true, global.handle_extended_frames),
+        ] {
+            let fifo = match handling {
+                NonMatchingFilter::IntoRxFifo0 => FIFONr::FIFO0,
+                NonMatchingFilter::IntoRxFifo1 => FIFONr::FIFO1,
+                NonMatchingFilter::Reject => continue,
+            };
+            if layout.rx_fifo_capacity(fifo) == 0 {
+                return Err(ConfigError::NonMatchingFramesToEmptyFifo { extended });
+            }
+        }
+
+        let ram = self.message_ram();
+        let len = layout.standard_filter_capacity();
+        if let Ok(elements) = ram.standard_filters(0, len as usize) {
+            for (index, element) in (0..len).zip(elements) {
+                let element = element.read();
+                let enabled = element.sft() != StandardFilterType::Disabled;
+                if !target_allocated(layout, element.sfec(), enabled, element.sfid2() as u32) {
+                    return Err(ConfigError::StandardFilterTargetMissing { index });
+                }
+            }
+        }
+        let len = layout.extended_filter_capacity();
+        if let Ok(elements) = ram.extended_filters(0, len as usize) {
+            for (index, element) in (0..len).zip(elements) {
+                let (f0, f1) = (element.f0.read(), element.f1.read());
+                if !target_allocated(layout, f0.efec(), true, f1.efid2()) {
+                    return Err(ConfigError::ExtendedFilterTargetMissing { index });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns `false` if a matching frame would be stored into storage with no elements. With "store into Rx Buffer"
+/// the filter type is ignored and ID2 bits 5:0 select the buffer.
+#[cfg(feature = "h7")]
+fn target_allocated(
+    layout: &MessageRamLayout,
+    config: StandardFilterConfiguration,
+    enabled: bool,
+    id2: u32,
+) -> bool {
+    match config {
+        StandardFilterConfiguration::StoreAsDebugMessage => {
+            (id2 & 0x3F) < layout.rx_buffer_count() as u32
+        }
+        _ if !enabled => true,
+        StandardFilterConfiguration::StoreInFIFO0
+        | StandardFilterConfiguration::SetPriorityAndStoreInFIFO0 => {
+            layout.rx_fifo_capacity(FIFONr::FIFO0) > 0
+        }
+        StandardFilterConfiguration::StoreInFIFO1
+        | StandardFilterConfiguration::SetPriorityAndStoreInFIFO1 => {
+            layout.rx_fifo_capacity(FIFONr::FIFO1) > 0
+        }
+        _ => true,
+    }
+}
+
+#[cfg(all(test, feature = "h7"))]
+mod tests {
+    use super::*;
+    use crate::FdCanInstance;
+    use crate::fdcan::ConfigMode;
+    use crate::fdcan::tests::host_fdcan;
+    use crate::message_ram_builder::MessageRamBuilder;
+    use crate::message_ram_layout::tests::{fifo_layout, host_ram};
+
+    fn filter() -> StandardFilter {
+        StandardFilter::classic(
+            StandardId::MAX,
+            0x7FF,
+            StandardFilterConfiguration::StoreInFIFO0,
+        )
+    }
+
+    #[test]
+    fn standard_filter_element_bits() {
+        // SFT[31:30], SFEC[29:27], SFID1[26:16], SFID2[10:0]
+        let id = |raw| StandardId::new(raw).unwrap();
+        let classic =
+            StandardFilter::classic(id(0x123), 0x7F0, StandardFilterConfiguration::StoreInFIFO1);
+        assert_eq!(classic.to_element().into_bits(), 0x9123_07F0);
+        let range =
+            StandardFilter::range(id(0x100), id(0x1FF), StandardFilterConfiguration::Reject);
+        assert_eq!(range.to_element().into_bits(), 0x1900_01FF);
+        assert_eq!(
+            StandardFilter::disabled().to_element().into_bits(),
+            0xC000_0000
+        );
+    }
+
+    #[test]
+    fn extended_filter_element_bits() {
+        // F0: EFEC[31:29], EFID1[28:0]; F1: EFT[31:30], EFID2[28:0]
+        let id = |raw| ExtendedId::new(raw).unwrap();
+        let (f0, f1) = ExtendedFilter::classic(
+            id(0x1234_5678),
+            0x1FFF_FF00,
+            ExtendedFilterConfiguration::StoreInFIFO0,
+        )
+        .to_elements();
+        assert_eq!((f0.into_bits(), f1.into_bits()), (0x3234_5678, 0x9FFF_FF00));
+        let (f0, f1) = ExtendedFilter::range(
+            id(0x100),
+            id(0x200),
+            ExtendedFilterConfiguration::StoreAsDebugMessage,
+        )
+        .to_elements();
+        assert_eq!((f0.into_bits(), f1.into_bits()), (0xE000_0100, 0xC000_0200));
+    }
+
+    #[test]
+    fn filters_before_set_layout_are_rejected() {
+        let mut can = host_fdcan::<ConfigMode>(FdCanInstance::FdCan1, host_ram());
+        assert!(matches!(
+            can.configure_standard_filter(0, filter()),
+            Err(Error::LayoutNotApplied)
+        ));
+        assert!(matches!(
+            can.configure_extended_filter(0, ExtendedFilter::disabled()),
+            Err(Error::LayoutNotApplied)
+        ));
+    }
+
+    #[test]
+    fn filters_after_set_layout_land_in_the_filter_list() {
+        let ram = host_ram();
+        let mut can = host_fdcan::<ConfigMode>(FdCanInstance::FdCan1, ram);
+        let builder = MessageRamBuilder::for_region(0x20, 0x400, FdCanInstance::FdCan1);
+        let (layout, _) = fifo_layout(builder, 2, 1, 1, 0);
+        can.set_layout(layout);
+        can.configure_standard_filter(1, filter()).unwrap();
+        // SAFETY: word 0x21 is inside the host message RAM buffer
+        let word = unsafe { ram.word_ptr(0x21).read() };
+        assert_eq!(word, filter().to_element().into_bits());
+        assert!(matches!(
+            can.configure_extended_filter(0, ExtendedFilter::disabled()),
+            Err(Error::FilterIndexOutOfRange)
+        ));
+    }
+}
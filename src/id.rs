@@ -36,7 +36,7 @@ impl StandardId {
 
     /// Returns this CAN Identifier as a raw 16-bit integer.
     #[inline]
-    pub fn as_raw(&self) -> u16 {
+    pub const fn as_raw(&self) -> u16 {
         self.0
     }
 }
@@ -78,7 +78,7 @@ impl ExtendedId {
 
     /// Returns this CAN Identifier as a raw 32-bit integer.
     #[inline]
-    pub fn as_raw(&self) -> u32 {
+    pub const fn as_raw(&self) -> u32 {
         self.0
     }
 
@@ -101,7 +101,54 @@ pub enum Id {
 }
 
 impl Id {
-    pub(crate) fn reg_value(&self) -> u32 {
+    /// Tries to create a standard `Id`, returns `None` if `raw` is out of range of an 11-bit integer.
+    #[inline]
+    pub const fn new_standard(raw: u16) -> Option<Self> {
+        match StandardId::new(raw) {
+            Some(id) => Some(Id::Standard(id)),
+            None => None,
+        }
+    }
+
+    /// Tries to create an extended `Id`, returns `None` if `raw` is out of range of a 29-bit integer.
+    #[inline]
+    pub const fn new_extended(raw: u32) -> Option<Self> {
+        match ExtendedId::new(raw) {
+            Some(id) => Some(Id::Extended(id)),
+            None => None,
+        }
+    }
+
+    /// Creates an `Id` from a raw right-aligned identifier value and the XTD flag, e.g. decoded from a received
+    /// element. Returns `None` if `raw` does not fit into 11 or 29 bits respectively, instead of truncating it.
+    #[inline]
+    pub const fn try_from_raw(raw: u32, extended: bool) -> Option<Self> {
+        if extended {
+            Self::new_extended(raw)
+        } else if raw <= StandardId::MAX.0 as u32 {
+            Self::new_standard(raw as u16)
+        } else {
+            None
+        }
+    }
+
+    /// Right-aligned identifier value, e.g. `0x123` for a standard ID, inverse of [try_from_raw](Self::try_from_raw).
+    #[inline]
+    pub const fn raw(&self) -> u32 {
+        match self {
+            Id::Standard(sid) => sid.0 as u32,
+            Id::Extended(eid) => eid.0,
+        }
+    }
+
+    /// Returns `true` for a 29-bit identifier, i.e. the IDE bit of the frame.
+    #[inline]
+    pub const fn is_extended(&self) -> bool {
+        matches!(self, Id::Extended(_))
+    }
+
+    /// ID field value of a TX/RX element: standard IDs are left-justified to bits 28:18, extended IDs use 28:0.
+    pub(crate) const fn reg_value(&self) -> u32 {
         const STANDARD_SHIFT: u32 = 18;
         match self {
             Id::Standard(sid) => (sid.0 as u32) << STANDARD_SHIFT,
@@ -171,6 +218,11 @@ impl IdReg {
         Self(id.as_raw() << Self::EXTENDED_SHIFT | (1 << Self::XTD_SHIFT))
     }
 
+    /// Creates an identifier from the first word of an RX buffer or Tx Event FIFO element (XTD, RTR and ID fields).
+    pub(crate) const fn from_element_word(word: u32) -> Self {
+        Self(word & (Self::XTD_MASK | Self::RTR_MASK | Self::EXTENDED_MASK))
+    }
+
     pub(crate) fn as_raw_id(&self) -> u32 {
         self.0 & Self::EXTENDED_MASK
     }
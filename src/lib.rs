@@ -1,11 +1,15 @@
 #![no_std]
+#![deny(unsafe_op_in_unsafe_fn)]
 
+#[cfg(feature = "h7")]
+pub mod ccu;
 pub mod config;
 #[cfg(feature = "h7")]
 pub mod message_ram_builder;
 pub mod pac_traits;
 
 pub mod fdcan;
+pub mod filter;
 pub mod pac;
 pub mod util;
 
@@ -15,19 +19,30 @@ pub mod asynchronous;
 pub mod embassy;
 pub mod id;
 mod message_ram_layout;
+#[cfg(feature = "h7")]
+pub mod ttcan;
+pub mod tx_event;
 pub mod tx_rx;
 
-pub use config::{DataBitTiming, NominalBitTiming};
+pub use config::{ConfigMismatch, DataBitTiming, ErrorCounters, InterruptMask, NominalBitTiming};
 pub use fdcan::{
     ConfigMode, Error, FdCan, FdCanInstance, FdCanInstances, FdCanInterrupt, InternalLoopbackMode,
-    PoweredDownMode,
+    Mode, PoweredDownMode,
 };
+pub use filter::{ConfigError, ExtendedFilter, StandardFilter};
 pub use id::{ExtendedId, Id, StandardId};
 #[cfg(feature = "h7")]
-pub use message_ram_builder::{MessageRamBuilder, MessageRamBuilderError, RamBuilderInitialState};
+pub use message_ram_builder::{
+    InstanceRamUsage, LayoutPlan, LayoutPlanError, LayoutPlanReport, MessageRamBuilder,
+    MessageRamBuilderError, RamBuilderInitialState,
+};
 #[cfg(feature = "h7")]
-pub use message_ram_layout::{DataFieldSize, MessageRamLayout, TxBufferIdx};
-pub use tx_rx::TxFrameHeader;
+pub use message_ram_layout::{DataFieldSize, LayoutRegion, LayoutReport, MessageRamLayout};
+pub use message_ram_layout::{FIFONr, TxBufferIdx};
+pub use tx_rx::FrameIter;
+#[cfg(feature = "stats")]
+pub use tx_rx::RxStats;
+pub use tx_rx::{RxFrame, RxFrameInfo, TxFrameHeader};
 
 // we must wait two peripheral clock cycles before the clock is active
 // http://efton.sk/STM32/gotcha/g183.html
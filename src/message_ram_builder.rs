@@ -1,7 +1,8 @@
 use crate::FdCanInstance;
+use crate::filter::{ExtendedFilter, StandardFilter};
 use crate::message_ram_layout::{DataFieldSize, MessageRamLayout, TxBufferIdx};
+use crate::pac::limits;
 use core::marker::PhantomData;
-use paste::paste;
 
 // The builder states below. Builder will go through these states step by step for consistency and
 // simplicity, though MCAN itself does not impose a particular order of various blocks.
@@ -17,8 +18,14 @@ pub struct TxBuffers;
 pub struct TriggerMemory;
 
 /// Message RAM partitioner.
+///
+/// Element limits of each step come from `pac::limits` of the selected chip. G0 and G4 have a fixed layout
+/// in hardware, so the builder is only available on chips with configurable message RAM.
 pub struct MessageRamBuilder<S> {
+    /// Next free word, addresses in the layout are word offsets from the message RAM start.
     pos: u16,
+    /// First word of the layout being built for the current instance, see [restart](Self::restart).
+    start: u16,
     end: u16,
     layout: MessageRamLayout,
     /// Used to track for which instance layout is being done and to issue TxBufferIdx-es.
@@ -26,29 +33,80 @@ pub struct MessageRamBuilder<S> {
     _phantom: PhantomData<S>,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum MessageRamBuilderError {
     TooManyElements,
     OutOfMemory,
     TooManyInstances,
+    /// Allocated RX and TX regions use different [DataFieldSize]-s,
+    /// see [MessageRamLayout::require_uniform_data_size].
+    MismatchedDataSize,
+    /// [relayout](MessageRamLayout::relayout) of a layout with nothing allocated, e.g. the one an instance has
+    /// before [set_layout](crate::FdCan::set_layout).
+    LayoutNotApplied,
 }
 
+/// Message RAM words available to the builder, shared by all instances.
+const CAPACITY_WORDS: u16 = crate::pac::FDCAN_MSGRAM_LEN_WORDS as u16 - 4;
+
 pub(crate) fn message_ram_builder()
 -> Result<MessageRamBuilder<ElevenBitFilters>, MessageRamBuilderError> {
-    let end = crate::pac::FDCAN_MSGRAM_LEN_WORDS as u16 - 4;
     Ok(MessageRamBuilder {
         pos: 0,
-        end,
+        start: 0,
+        end: CAPACITY_WORDS,
         layout: MessageRamLayout::default(),
         instance: Some(FdCanInstance::FdCan1),
         _phantom: Default::default(),
     })
 }
 
+impl MessageRamBuilder<ElevenBitFilters> {
+    /// Builder allocating from words `[start, end)` only, see [MessageRamLayout::relayout].
+    pub(crate) const fn for_region(start: u16, end: u16, instance: FdCanInstance) -> Self {
+        MessageRamBuilder {
+            pos: start,
+            start,
+            end,
+            layout: MessageRamLayout::default(),
+            instance: Some(instance),
+            _phantom: PhantomData,
+        }
+    }
+}
+
 impl<S> MessageRamBuilder<S> {
+    /// Number of message RAM words allocated so far, including layouts of previous instances.
+    pub const fn words_used(&self) -> u16 {
+        self.pos
+    }
+
+    /// Number of message RAM words still available for allocation.
+    pub const fn words_free(&self) -> u16 {
+        self.end - self.pos
+    }
+
+    /// Drops everything allocated for the current instance and starts over from its first step, e.g. to fall back
+    /// to a smaller layout after [OutOfMemory](MessageRamBuilderError::OutOfMemory). Layouts of previous instances
+    /// and the instance the next layout is for are kept.
+    ///
+    /// [TxBufferIdx]-es handed out by the abandoned attempt must not be used, the new layout hands out its own.
+    pub const fn restart(self) -> MessageRamBuilder<ElevenBitFilters> {
+        MessageRamBuilder {
+            pos: self.start,
+            start: self.start,
+            end: self.end,
+            layout: MessageRamLayout::default(),
+            instance: self.instance,
+            _phantom: PhantomData,
+        }
+    }
+
     const fn into_state<S2>(self) -> MessageRamBuilder<S2> {
         MessageRamBuilder {
             pos: self.pos,
+            start: self.start,
             end: self.end,
             layout: self.layout,
             instance: self.instance,
@@ -58,24 +116,22 @@ impl<S> MessageRamBuilder<S> {
 }
 
 macro_rules! check_and_advance {
-    ($self:ident, $max_elements:expr, $len:expr, $element_size_words:expr, $dest:ident) => {
+    ($self:ident, $max_elements:expr, $len:expr, $element_size_words:expr, $addr:ident, $len_field:ident) => {
         if $len > $max_elements {
             return Err(MessageRamBuilderError::TooManyElements);
         }
-        let new_pos = $self.pos + ($len as u16) * $element_size_words * 4;
+        let new_pos = $self.pos + ($len as u16) * $element_size_words;
         if new_pos > $self.end {
             return Err(MessageRamBuilderError::OutOfMemory);
         }
-        paste! {
-            $self.layout.[<$dest _addr>] = $self.pos;
-            $self.layout.[<$dest _len>] = $len;
-        }
+        $self.layout.$addr = $self.pos;
+        $self.layout.$len_field = $len;
         $self.pos = new_pos;
     };
 }
 
 impl MessageRamBuilder<ElevenBitFilters> {
-    const MAX_ELEMENTS: u8 = 128;
+    const MAX_ELEMENTS: u8 = limits::ELEVEN_BIT_FILTERS;
 
     /// Allocate zero or more 11-bit filters and move to the next step.
     pub const fn allocate_11bit_filters(
@@ -85,10 +141,37 @@ impl MessageRamBuilder<ElevenBitFilters> {
         if self.instance.is_none() {
             return Err(MessageRamBuilderError::TooManyInstances);
         }
-        check_and_advance!(self, Self::MAX_ELEMENTS, len, 1, eleven_bit_filters);
+        check_and_advance!(
+            self,
+            Self::MAX_ELEMENTS,
+            len,
+            1,
+            eleven_bit_filters_addr,
+            eleven_bit_filters_len
+        );
         Ok(self.into_state())
     }
 
+    /// Allocate one 11-bit filter per element of `filters` and move to the next step.
+    ///
+    /// Filters are written to message RAM when the resulting layout is applied, so a static filter table
+    /// only has to be declared once.
+    pub const fn allocate_11bit_filters_init(
+        self,
+        filters: &'static [StandardFilter],
+    ) -> Result<MessageRamBuilder<TwentyNineBitFilters>, MessageRamBuilderError> {
+        if filters.len() > Self::MAX_ELEMENTS as usize {
+            return Err(MessageRamBuilderError::TooManyElements);
+        }
+        match self.allocate_11bit_filters(filters.len() as u8) {
+            Ok(mut b) => {
+                b.layout.eleven_bit_filters_init = filters;
+                Ok(b)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
     /// Merge this builder with the other. Useful if doing full re-init and re-layout of multiple CAN instances.
     pub fn recombine(&mut self, _other: MessageRamBuilder<ElevenBitFilters>) {
         todo!()
@@ -96,20 +179,46 @@ impl MessageRamBuilder<ElevenBitFilters> {
 }
 
 impl MessageRamBuilder<TwentyNineBitFilters> {
-    const MAX_ELEMENTS: u8 = 64;
+    const MAX_ELEMENTS: u8 = limits::TWENTY_NINE_BIT_FILTERS;
 
     /// Allocate zero or more 29-bit filters and move to the next step.
     pub const fn allocate_29bit_filters(
         mut self,
         len: u8,
     ) -> Result<MessageRamBuilder<RxFifo0>, MessageRamBuilderError> {
-        check_and_advance!(self, Self::MAX_ELEMENTS, len, 2, twenty_nine_bit_filters);
+        check_and_advance!(
+            self,
+            Self::MAX_ELEMENTS,
+            len,
+            2,
+            twenty_nine_bit_filters_addr,
+            twenty_nine_bit_filters_len
+        );
         Ok(self.into_state())
     }
+
+    /// Allocate one 29-bit filter per element of `filters` and move to the next step.
+    ///
+    /// Filters are written to message RAM when the resulting layout is applied.
+    pub const fn allocate_29bit_filters_init(
+        self,
+        filters: &'static [ExtendedFilter],
+    ) -> Result<MessageRamBuilder<RxFifo0>, MessageRamBuilderError> {
+        if filters.len() > Self::MAX_ELEMENTS as usize {
+            return Err(MessageRamBuilderError::TooManyElements);
+        }
+        match self.allocate_29bit_filters(filters.len() as u8) {
+            Ok(mut b) => {
+                b.layout.twenty_nine_bit_filters_init = filters;
+                Ok(b)
+            }
+            Err(e) => Err(e),
+        }
+    }
 }
 
 impl MessageRamBuilder<RxFifo0> {
-    const MAX_ELEMENTS: u8 = 64;
+    const MAX_ELEMENTS: u8 = limits::RX_FIFO;
 
     /// Allocate zero or more RX FIFO0 elements and move to the next step.
     pub const fn allocate_rx_fifo0_buffers(
@@ -122,7 +231,8 @@ impl MessageRamBuilder<RxFifo0> {
             Self::MAX_ELEMENTS,
             len,
             2 + data_size.words(),
-            rx_fifo0
+            rx_fifo0_addr,
+            rx_fifo0_len
         );
         self.layout.rx_fifo0_data_size = data_size;
         Ok(self.into_state())
@@ -130,7 +240,7 @@ impl MessageRamBuilder<RxFifo0> {
 }
 
 impl MessageRamBuilder<RxFifo1> {
-    const MAX_ELEMENTS: u8 = 64;
+    const MAX_ELEMENTS: u8 = limits::RX_FIFO;
 
     /// Allocate zero or more RX FIFO1 elements and move to the next step.
     pub const fn allocate_rx_fifo1_buffers(
@@ -143,7 +253,8 @@ impl MessageRamBuilder<RxFifo1> {
             Self::MAX_ELEMENTS,
             len,
             2 + data_size.words(),
-            rx_fifo1
+            rx_fifo1_addr,
+            rx_fifo1_len
         );
         self.layout.rx_fifo1_data_size = data_size;
         Ok(self.into_state())
@@ -151,7 +262,7 @@ impl MessageRamBuilder<RxFifo1> {
 }
 
 impl MessageRamBuilder<RxBuffers> {
-    const MAX_ELEMENTS: u8 = 64;
+    const MAX_ELEMENTS: u8 = limits::RX_BUFFERS;
 
     /// Allocate dedicated RX buffers space and move to the next step.
     pub const fn allocate_rx_buffers(
@@ -164,7 +275,8 @@ impl MessageRamBuilder<RxBuffers> {
             Self::MAX_ELEMENTS,
             len,
             2 + data_size.words(),
-            rx_buffers
+            rx_buffers_addr,
+            rx_buffers_len
         );
         self.layout.rx_buffers_data_size = data_size;
         Ok(self.into_state())
@@ -177,14 +289,21 @@ impl MessageRamBuilder<RxBuffers> {
 }
 
 impl MessageRamBuilder<TxEventFifo> {
-    const MAX_ELEMENTS: u8 = 32;
+    const MAX_ELEMENTS: u8 = limits::TX_EVENT_FIFO;
 
     /// Allocate zero or more TX Event FIFO elements and move to the next step.
     pub const fn allocate_tx_event_fifo_buffers(
         mut self,
         len: u8,
     ) -> Result<MessageRamBuilder<TxBufferElementSize>, MessageRamBuilderError> {
-        check_and_advance!(self, Self::MAX_ELEMENTS, len, 2, tx_event_fifo);
+        check_and_advance!(
+            self,
+            Self::MAX_ELEMENTS,
+            len,
+            2,
+            tx_event_fifo_addr,
+            tx_event_fifo_len
+        );
         Ok(self.into_state())
     }
 }
@@ -200,7 +319,7 @@ impl MessageRamBuilder<TxBufferElementSize> {
 }
 
 impl MessageRamBuilder<TxBuffers> {
-    const MAX_ELEMENTS: u8 = 32;
+    const MAX_ELEMENTS: u8 = limits::TX_BUFFERS;
 
     /// Allocate dedicated TX buffer and get a TxBufferIdx that can be later used to interact with it.
     pub const fn allocate_dedicated_tx_buffer(
@@ -223,21 +342,28 @@ impl MessageRamBuilder<TxBuffers> {
         mut self,
         fifo_or_queue_len: u8,
     ) -> Result<MessageRamBuilder<TriggerMemory>, MessageRamBuilderError> {
-        let len = fifo_or_queue_len + self.layout.tx_buffers_len;
+        let dedicated_len = self.layout.tx_buffers_len;
+        let Some(len) = fifo_or_queue_len.checked_add(dedicated_len) else {
+            return Err(MessageRamBuilderError::TooManyElements);
+        };
         check_and_advance!(
             self,
             Self::MAX_ELEMENTS,
             len,
             2 + self.layout.tx_buffers_data_size.words(),
-            tx_buffers
+            tx_buffers_addr,
+            tx_buffers_len
         );
+        // Space is allocated for both, but NDTB must only hold the number of dedicated buffers,
+        // FIFO/Queue buffers are placed right after them.
+        self.layout.tx_buffers_len = dedicated_len;
         self.layout.tx_fifo_or_queue_len = fifo_or_queue_len;
         Ok(self.into_state())
     }
 }
 
 impl MessageRamBuilder<TriggerMemory> {
-    const MAX_ELEMENTS: u8 = 64;
+    const MAX_ELEMENTS: u8 = limits::TRIGGER_MEMORY;
 
     /// Allocate zero or more trigger elements and get a MessageRamLayout.
     /// Also get a MessageRamBuilder in initial state to build layouts for other instances, if any.
@@ -246,8 +372,16 @@ impl MessageRamBuilder<TriggerMemory> {
         len: u8,
     ) -> Result<(MessageRamLayout, MessageRamBuilder<ElevenBitFilters>), MessageRamBuilderError>
     {
-        check_and_advance!(self, Self::MAX_ELEMENTS, len, 2, trigger_memory);
+        check_and_advance!(
+            self,
+            Self::MAX_ELEMENTS,
+            len,
+            2,
+            trigger_memory_addr,
+            trigger_memory_len
+        );
         let layout = self.layout;
+        self.start = self.pos;
         let next_instance = match self.instance.expect("checked on step one") {
             FdCanInstance::FdCan1 => Some(FdCanInstance::FdCan2),
             FdCanInstance::FdCan2 => Some(FdCanInstance::FdCan3),
@@ -258,6 +392,131 @@ impl MessageRamBuilder<TriggerMemory> {
     }
 }
 
+/// Layouts of all instances collected up front, so that the complete multi-instance plan can be checked with
+/// [validate](Self::validate) before any of them is applied.
+///
+/// Useful when layouts don't all come from one builder chain, e.g. when some of them are
+/// [re-laid out](MessageRamLayout::relayout) or built in different places.
+#[derive(Copy, Clone, Debug)]
+pub struct LayoutPlan {
+    layouts: [Option<MessageRamLayout>; 3],
+}
+
+/// Message RAM words `[start, end)` taken by one instance of a [LayoutPlan].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InstanceRamUsage {
+    pub instance: FdCanInstance,
+    pub start: u16,
+    pub end: u16,
+}
+
+impl InstanceRamUsage {
+    /// Number of words taken.
+    #[inline]
+    pub const fn words(&self) -> u16 {
+        self.end - self.start
+    }
+}
+
+/// Result of a successful [LayoutPlan::validate].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LayoutPlanReport {
+    /// Indexed by instance, `None` for instances without a layout in the plan
+    pub instances: [Option<InstanceRamUsage>; 3],
+    pub words_used: u16,
+    pub words_free: u16,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LayoutPlanError {
+    /// Layout of `instance` ends past the message RAM
+    OutOfMemory { instance: FdCanInstance, end: u16 },
+    /// Layouts of the two instances share at least one word
+    Overlap {
+        first: InstanceRamUsage,
+        second: InstanceRamUsage,
+    },
+}
+
+impl LayoutPlan {
+    pub const fn new() -> Self {
+        LayoutPlan { layouts: [None; 3] }
+    }
+
+    /// Adds or replaces the layout of `instance`.
+    pub const fn with(mut self, instance: FdCanInstance, layout: MessageRamLayout) -> Self {
+        self.layouts[Self::index(instance)] = Some(layout);
+        self
+    }
+
+    /// Checks that every layout fits into message RAM and that no two layouts overlap, returning per-instance
+    /// RAM usage on success. Empty layouts take no RAM and never overlap.
+    pub const fn validate(&self) -> Result<LayoutPlanReport, LayoutPlanError> {
+        const INSTANCES: [FdCanInstance; 3] = [
+            FdCanInstance::FdCan1,
+            FdCanInstance::FdCan2,
+            FdCanInstance::FdCan3,
+        ];
+        let mut instances = [None; 3];
+        let mut words_used = 0;
+        let mut i = 0;
+        while i < self.layouts.len() {
+            if let Some(layout) = &self.layouts[i] {
+                let (start, end) = layout.region();
+                let usage = InstanceRamUsage {
+                    instance: INSTANCES[i],
+                    start,
+                    end,
+                };
+                if end > CAPACITY_WORDS {
+                    return Err(LayoutPlanError::OutOfMemory {
+                        instance: usage.instance,
+                        end,
+                    });
+                }
+                let mut j = 0;
+                while j < i {
+                    if let Some(other) = instances[j] {
+                        let other: InstanceRamUsage = other;
+                        if usage.start < other.end && other.start < usage.end {
+                            return Err(LayoutPlanError::Overlap {
+                                first: other,
+                                second: usage,
+                            });
+                        }
+                    }
+                    j += 1;
+                }
+                words_used += usage.words();
+                instances[i] = Some(usage);
+            }
+            i += 1;
+        }
+        Ok(LayoutPlanReport {
+            instances,
+            words_used,
+            words_free: CAPACITY_WORDS - words_used,
+        })
+    }
+
+    const fn index(instance: FdCanInstance) -> usize {
+        match instance {
+            FdCanInstance::FdCan1 => 0,
+            FdCanInstance::FdCan2 => 1,
+            FdCanInstance::FdCan3 => 2,
+        }
+    }
+}
+
+impl Default for LayoutPlan {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 macro_rules! unwrap_or_return {
     ($expr:expr) => {
         match $expr {
@@ -267,6 +526,8 @@ macro_rules! unwrap_or_return {
     };
 }
 
+/// Layout for CAN FD with 64 byte elements: one filter of each kind, one RX FIFO0 element, one TX FIFO element
+/// and one TX event. Returns [MessageRamBuilderError::OutOfMemory] if there is not enough RAM left.
 pub const fn basic_layout(
     builder: MessageRamBuilder<RamBuilderInitialState>,
 ) -> Result<(MessageRamLayout, MessageRamBuilder<RamBuilderInitialState>), MessageRamBuilderError> {
@@ -281,3 +542,253 @@ pub const fn basic_layout(
     let (layout, builder) = unwrap_or_return!(b.allocate_triggers(0));
     Ok((layout, builder))
 }
+
+/// Layout for classic CAN with 8 byte elements, trading data size for FIFO depth: one filter of each kind,
+/// 16 RX FIFO0 elements, 8 TX FIFO elements and 8 TX events.
+/// Returns [MessageRamBuilderError::OutOfMemory] if there is not enough RAM left.
+pub const fn basic_layout_classic(
+    builder: MessageRamBuilder<RamBuilderInitialState>,
+) -> Result<(MessageRamLayout, MessageRamBuilder<RamBuilderInitialState>), MessageRamBuilderError> {
+    let b = unwrap_or_return!(builder.allocate_11bit_filters(1));
+    let b = unwrap_or_return!(b.allocate_29bit_filters(1));
+    let b = unwrap_or_return!(b.allocate_rx_fifo0_buffers(16, DataFieldSize::_8Bytes));
+    let b = unwrap_or_return!(b.allocate_rx_fifo1_buffers(0, DataFieldSize::_8Bytes));
+    let b = b.skip_dedicated_buffers();
+    let b = unwrap_or_return!(b.allocate_tx_event_fifo_buffers(8));
+    let b = b.tx_buffer_element_size(DataFieldSize::_8Bytes);
+    let b = unwrap_or_return!(b.allocate_fifo_or_queue(8));
+    let (layout, builder) = unwrap_or_return!(b.allocate_triggers(0));
+    Ok((layout, builder))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rx_buffer_and_fifo_element_sizes_are_independent() {
+        // Dedicated RX buffers and RX FIFOs have independent element sizes (RXESC.RBDS vs RXESC.FnDS): an 8 byte
+        // FIFO0 followed by 64 byte dedicated buffers must keep both sizes and strides through the remaining steps.
+        let (layout, _) = MessageRamBuilder::for_region(0, 512, FdCanInstance::FdCan1)
+            .allocate_11bit_filters(0)
+            .unwrap()
+            .allocate_29bit_filters(0)
+            .unwrap()
+            .allocate_rx_fifo0_buffers(4, DataFieldSize::_8Bytes)
+            .unwrap()
+            .allocate_rx_fifo1_buffers(0, DataFieldSize::_12Bytes)
+            .unwrap()
+            .allocate_rx_buffers(2, DataFieldSize::_64Bytes)
+            .unwrap()
+            .allocate_tx_event_fifo_buffers(0)
+            .unwrap()
+            .tx_buffer_element_size(DataFieldSize::_16Bytes)
+            .allocate_fifo_or_queue(1)
+            .unwrap()
+            .allocate_triggers(0)
+            .unwrap();
+        assert!(matches!(layout.rx_fifo0_data_size, DataFieldSize::_8Bytes));
+        assert!(matches!(
+            layout.rx_buffers_data_size,
+            DataFieldSize::_64Bytes
+        ));
+        assert_eq!(layout.rx_buffers_addr, layout.rx_fifo0_addr + 4 * (2 + 2));
+        assert_eq!(
+            layout.tx_buffers_addr,
+            layout.rx_buffers_addr + 2 * (2 + 16)
+        );
+    }
+
+    #[test]
+    fn dedicated_tx_buffers_are_allocated_without_fifo() {
+        // Dedicated TX buffers only get their RAM when moving past the TX buffers step, which is the only way out
+        // of it: with no FIFO/Queue elements the cursor must still advance past all of them, so trigger memory
+        // placed right after does not overlap.
+        let mut b = MessageRamBuilder::for_region(0, 512, FdCanInstance::FdCan1)
+            .allocate_11bit_filters(0)
+            .unwrap()
+            .allocate_29bit_filters(0)
+            .unwrap()
+            .allocate_rx_fifo0_buffers(0, DataFieldSize::_8Bytes)
+            .unwrap()
+            .allocate_rx_fifo1_buffers(0, DataFieldSize::_8Bytes)
+            .unwrap()
+            .skip_dedicated_buffers()
+            .allocate_tx_event_fifo_buffers(0)
+            .unwrap()
+            .tx_buffer_element_size(DataFieldSize::_64Bytes);
+        for _ in 0..5 {
+            (_, b) = b.allocate_dedicated_tx_buffer().unwrap();
+        }
+        let (layout, _) = b
+            .allocate_fifo_or_queue(0)
+            .unwrap()
+            .allocate_triggers(2)
+            .unwrap();
+        assert_eq!(layout.tx_buffers_len, 5);
+        assert_eq!(layout.tx_fifo_or_queue_len, 0);
+        assert_eq!(
+            layout.trigger_memory_addr,
+            layout.tx_buffers_addr + 5 * (2 + 16)
+        );
+    }
+
+    #[test]
+    fn uniform_data_size_ignores_empty_regions() {
+        // Only allocated regions are looked at: an empty FIFO1 keeps its default size without tripping the check,
+        // while a dedicated RX buffer of a different size does.
+        let build = |rx_buffers| {
+            let (layout, _) = MessageRamBuilder::for_region(0, 512, FdCanInstance::FdCan1)
+                .allocate_11bit_filters(0)
+                .unwrap()
+                .allocate_29bit_filters(0)
+                .unwrap()
+                .allocate_rx_fifo0_buffers(4, DataFieldSize::_64Bytes)
+                .unwrap()
+                .allocate_rx_fifo1_buffers(0, DataFieldSize::_8Bytes)
+                .unwrap()
+                .allocate_rx_buffers(rx_buffers, DataFieldSize::_8Bytes)
+                .unwrap()
+                .allocate_tx_event_fifo_buffers(0)
+                .unwrap()
+                .tx_buffer_element_size(DataFieldSize::_64Bytes)
+                .allocate_fifo_or_queue(1)
+                .unwrap()
+                .allocate_triggers(0)
+                .unwrap();
+            layout.require_uniform_data_size()
+        };
+        assert!(build(0).is_ok());
+        assert!(matches!(
+            build(1),
+            Err(MessageRamBuilderError::MismatchedDataSize)
+        ));
+    }
+
+    /// 4 standard filters, 4 element RX FIFO0 and 2 element TX FIFO, all with 8 byte elements.
+    fn small_layout(
+        b: MessageRamBuilder<ElevenBitFilters>,
+    ) -> (MessageRamLayout, MessageRamBuilder<ElevenBitFilters>) {
+        b.allocate_11bit_filters(4)
+            .unwrap()
+            .allocate_29bit_filters(0)
+            .unwrap()
+            .allocate_rx_fifo0_buffers(4, DataFieldSize::_8Bytes)
+            .unwrap()
+            .allocate_rx_fifo1_buffers(0, DataFieldSize::_8Bytes)
+            .unwrap()
+            .allocate_rx_buffers(0, DataFieldSize::_8Bytes)
+            .unwrap()
+            .allocate_tx_event_fifo_buffers(0)
+            .unwrap()
+            .tx_buffer_element_size(DataFieldSize::_8Bytes)
+            .allocate_fifo_or_queue(2)
+            .unwrap()
+            .allocate_triggers(0)
+            .unwrap()
+    }
+
+    #[test]
+    fn layout_plan_catches_overlap_and_overflow() {
+        // Two layouts from one builder chain pass, reusing the first one for another instance is caught as an
+        // overlap and a layout past the end of message RAM as running out of memory.
+        let (first, b) = small_layout(MessageRamBuilder::for_region(
+            0,
+            CAPACITY_WORDS,
+            FdCanInstance::FdCan1,
+        ));
+        let (second, _) = small_layout(b);
+        let plan = LayoutPlan::new()
+            .with(FdCanInstance::FdCan1, first)
+            .with(FdCanInstance::FdCan2, second);
+        let report = plan.validate().unwrap();
+        assert!(report.instances[2].is_none());
+        assert_eq!(report.words_used, 2 * (4 + 4 * 4 + 2 * 4));
+        assert_eq!(report.words_used + report.words_free, CAPACITY_WORDS);
+
+        let overlapping = plan.with(FdCanInstance::FdCan3, first);
+        assert!(matches!(
+            overlapping.validate(),
+            Err(LayoutPlanError::Overlap { .. })
+        ));
+
+        let (past_end, _) = small_layout(MessageRamBuilder::for_region(
+            CAPACITY_WORDS - 10,
+            CAPACITY_WORDS + 100,
+            FdCanInstance::FdCan3,
+        ));
+        assert!(matches!(
+            plan.with(FdCanInstance::FdCan3, past_end).validate(),
+            Err(LayoutPlanError::OutOfMemory {
+                instance: FdCanInstance::FdCan3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn restart_frees_the_current_instance() {
+        // A retry after a partial allocation lands at the same addresses as a fresh builder and the instance
+        // cursor is unchanged.
+        let b = MessageRamBuilder::for_region(10, 512, FdCanInstance::FdCan2)
+            .allocate_11bit_filters(100)
+            .unwrap()
+            .allocate_29bit_filters(50)
+            .unwrap();
+        let (layout, next) = b
+            .restart()
+            .allocate_11bit_filters(4)
+            .unwrap()
+            .allocate_29bit_filters(0)
+            .unwrap()
+            .allocate_rx_fifo0_buffers(1, DataFieldSize::_8Bytes)
+            .unwrap()
+            .allocate_rx_fifo1_buffers(0, DataFieldSize::_8Bytes)
+            .unwrap()
+            .allocate_rx_buffers(0, DataFieldSize::_8Bytes)
+            .unwrap()
+            .allocate_tx_event_fifo_buffers(0)
+            .unwrap()
+            .tx_buffer_element_size(DataFieldSize::_8Bytes)
+            .allocate_fifo_or_queue(1)
+            .unwrap()
+            .allocate_triggers(0)
+            .unwrap();
+        assert_eq!(layout.eleven_bit_filters_addr, 10);
+        assert_eq!(layout.rx_fifo0_addr, 14);
+        assert_eq!(next.words_used(), 14 + 4 + 4);
+        assert!(matches!(next.instance, Some(FdCanInstance::FdCan3)));
+        // Restarting right after a finished layout is a no-op for the next instance
+        assert_eq!(next.restart().words_used(), 22);
+    }
+
+    #[test]
+    fn relayout_stays_within_the_old_region() {
+        // Re-laying out the second instance must leave the neighbours untouched
+        let b = MessageRamBuilder::for_region(0, 2560, FdCanInstance::FdCan1);
+        let (_, b) = basic_layout(b).unwrap();
+        let (second, b) = basic_layout_classic(b).unwrap();
+        let (third, _) = basic_layout(b).unwrap();
+        let (relaid, _) = second
+            .relayout(FdCanInstance::FdCan2)
+            .unwrap()
+            .allocate_11bit_filters(8)
+            .unwrap()
+            .allocate_29bit_filters(0)
+            .unwrap()
+            .allocate_rx_fifo0_buffers(2, DataFieldSize::_64Bytes)
+            .unwrap()
+            .allocate_rx_fifo1_buffers(0, DataFieldSize::_8Bytes)
+            .unwrap()
+            .skip_dedicated_buffers()
+            .allocate_tx_event_fifo_buffers(0)
+            .unwrap()
+            .tx_buffer_element_size(DataFieldSize::_64Bytes)
+            .allocate_fifo_or_queue(2)
+            .unwrap()
+            .allocate_triggers(0)
+            .unwrap();
+        assert_eq!(relaid.region().0, second.region().0);
+        assert!(relaid.region().1 <= third.region().0);
+    }
+}
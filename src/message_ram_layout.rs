@@ -1,7 +1,17 @@
+#[cfg(feature = "h7")]
+use crate::filter::{ExtendedFilter, StandardFilter};
 use crate::pac::message_ram::{
     EventFIFOControl, Rtr, TimeStampCaptureEnable, TxBufferElementT0, TxBufferElementT1,
 };
-use crate::pac_traits::{RW, Reg};
+#[cfg(feature = "h7")]
+use crate::pac::message_ram::{
+    ExtendedFilterElementF0, ExtendedFilterElementF1, StandardFilterElement,
+    TriggerMemoryElementT0, TriggerMemoryElementT1,
+};
+use crate::pac::message_ram::{
+    RxBufferElementR0, RxBufferElementR1, TxEventFifoElementE0, TxEventFifoElementE1,
+};
+use crate::pac_traits::{R, RW, Reg};
 use crate::tx_rx::{Dlc, TxFrameHeader};
 use crate::{Error, FdCan, FdCanInstance};
 
@@ -9,14 +19,17 @@ use crate::{Error, FdCan, FdCanInstance};
 ///
 /// Note: only if core supports it, for example, G0 and G4 have fixed layout.
 #[cfg(feature = "h7")]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MessageRamLayout {
     pub(crate) eleven_bit_filters_addr: u16,
     pub(crate) eleven_bit_filters_len: u8,
+    /// Filters written to RAM when the layout is applied, the rest of the list is left disabled
+    pub(crate) eleven_bit_filters_init: &'static [StandardFilter],
 
     pub(crate) twenty_nine_bit_filters_addr: u16,
     pub(crate) twenty_nine_bit_filters_len: u8,
+    pub(crate) twenty_nine_bit_filters_init: &'static [ExtendedFilter],
 
     pub(crate) rx_fifo0_addr: u16,
     pub(crate) rx_fifo0_len: u8,
@@ -51,8 +64,10 @@ impl MessageRamLayout {
         Self {
             eleven_bit_filters_addr: 0,
             eleven_bit_filters_len: 0,
+            eleven_bit_filters_init: &[],
             twenty_nine_bit_filters_addr: 0,
             twenty_nine_bit_filters_len: 0,
+            twenty_nine_bit_filters_init: &[],
             rx_fifo0_addr: 0,
             rx_fifo0_len: 0,
             rx_fifo0_data_size: DataFieldSize::_8Bytes,
@@ -75,25 +90,234 @@ impl MessageRamLayout {
             trigger_memory_len: 0,
         }
     }
+
+    /// Nothing is allocated, which is what an instance has until [set_layout](crate::FdCan::set_layout) is
+    /// called.
+    pub(crate) const fn is_empty(&self) -> bool {
+        self.eleven_bit_filters_len == 0
+            && self.twenty_nine_bit_filters_len == 0
+            && self.rx_fifo0_len == 0
+            && self.rx_fifo1_len == 0
+            && self.rx_buffers_len == 0
+            && self.tx_event_fifo_len == 0
+            && self.tx_buffers_len == 0
+            && self.tx_fifo_or_queue_len == 0
+            && self.trigger_memory_len == 0
+    }
 }
 
 #[cfg(feature = "h7")]
 impl MessageRamLayout {
-    // Turn this layout back into builder, useful if doing re-init of just one CAN instance, without touching others.
-    pub fn relayout(
+    /// Turn this layout back into a builder limited to the RAM region it occupies, useful if doing re-init of
+    /// just one CAN instance, without touching others.
+    ///
+    /// On chips where instances share message RAM, the workflow for one instance is:
+    /// ```ignore
+    /// let old = *can.layout();
+    /// let (new, _) = old.relayout(FdCanInstance::FdCan2)?.allocate_11bit_filters(4)?...allocate_triggers(0)?;
+    /// let mut can = can.into_powered_down()?.into_config_mode_preserving_ram()?;
+    /// can.clear_layout_ram()?; // stale elements of the old layout must not be read as filters
+    /// can.set_layout(new);
+    /// ```
+    /// Only this instance's configuration registers and region are written, frames staged by other instances
+    /// survive.
+    ///
+    /// An empty layout covers no region to reuse,
+    /// [LayoutNotApplied](crate::message_ram_builder::MessageRamBuilderError::LayoutNotApplied) is returned.
+    pub const fn relayout(
         self,
-    ) -> crate::message_ram_builder::MessageRamBuilder<
-        crate::message_ram_builder::RamBuilderInitialState,
+        instance: FdCanInstance,
+    ) -> Result<
+        crate::message_ram_builder::MessageRamBuilder<
+            crate::message_ram_builder::RamBuilderInitialState,
+        >,
+        crate::message_ram_builder::MessageRamBuilderError,
     > {
-        // pos: first non zero start, end: last non zero start+size?
-        todo!()
+        if self.is_empty() {
+            return Err(crate::message_ram_builder::MessageRamBuilderError::LayoutNotApplied);
+        }
+        let (start, end) = self.region();
+        Ok(crate::message_ram_builder::MessageRamBuilder::for_region(
+            start, end, instance,
+        ))
+    }
+
+    /// Word offsets `[start, end)` of message RAM covered by this layout.
+    ///
+    /// The builder places all sections back to back starting with 11-bit filters and ending with trigger
+    /// memory, and records the start address of empty sections as well.
+    pub(crate) const fn region(&self) -> (u16, u16) {
+        let start = self.eleven_bit_filters_addr;
+        let end = self.trigger_memory_addr + self.trigger_memory_len as u16 * 2;
+        (start, end)
     }
+
+    /// Opt-in check that all allocated RX FIFOs, dedicated RX buffers and TX buffers use the same
+    /// [DataFieldSize], returns the layout back if so.
+    ///
+    /// Sizes of regions with no elements are ignored. Mixing sizes is allowed by the hardware and the builder,
+    /// this is for users who want to rule it out, e.g. right after `allocate_triggers`:
+    /// ```ignore
+    /// let (layout, tx_buffers) = builder...allocate_triggers(0)?;
+    /// let layout = layout.require_uniform_data_size()?;
+    /// ```
+    pub const fn require_uniform_data_size(
+        self,
+    ) -> Result<Self, crate::message_ram_builder::MessageRamBuilderError> {
+        let regions = [
+            (self.rx_fifo0_len, self.rx_fifo0_data_size),
+            (self.rx_fifo1_len, self.rx_fifo1_data_size),
+            (self.rx_buffers_len, self.rx_buffers_data_size),
+            (
+                self.tx_buffers_len + self.tx_fifo_or_queue_len,
+                self.tx_buffers_data_size,
+            ),
+        ];
+        let mut size: Option<DataFieldSize> = None;
+        let mut i = 0;
+        while i < regions.len() {
+            let (len, region_size) = regions[i];
+            i += 1;
+            if len == 0 {
+                continue;
+            }
+            match size {
+                None => size = Some(region_size),
+                Some(size) if size as u8 != region_size as u8 => {
+                    return Err(
+                        crate::message_ram_builder::MessageRamBuilderError::MismatchedDataSize,
+                    );
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(self)
+    }
+
+    /// Number of 11-bit filter elements that can be configured.
+    #[inline]
+    pub const fn standard_filter_capacity(&self) -> u8 {
+        self.eleven_bit_filters_len
+    }
+
+    /// Number of 29-bit filter elements that can be configured.
+    #[inline]
+    pub const fn extended_filter_capacity(&self) -> u8 {
+        self.twenty_nine_bit_filters_len
+    }
+
+    /// Number of elements in RX FIFO0 or FIFO1.
+    #[inline]
+    pub const fn rx_fifo_capacity(&self, fifo: FIFONr) -> u8 {
+        match fifo {
+            FIFONr::FIFO0 => self.rx_fifo0_len,
+            FIFONr::FIFO1 => self.rx_fifo1_len,
+        }
+    }
+
+    /// Number of dedicated RX buffers.
+    #[inline]
+    pub const fn rx_buffer_count(&self) -> u8 {
+        self.rx_buffers_len
+    }
+
+    /// Number of TX event FIFO elements.
+    #[inline]
+    pub const fn tx_event_fifo_capacity(&self) -> u8 {
+        self.tx_event_fifo_len
+    }
+
+    /// Number of dedicated TX buffers.
+    #[inline]
+    pub const fn tx_buffer_count(&self) -> u8 {
+        self.tx_buffers_len
+    }
+
+    /// Number of TX FIFO/Queue elements.
+    #[inline]
+    pub const fn tx_fifo_or_queue_capacity(&self) -> u8 {
+        self.tx_fifo_or_queue_len
+    }
+
+    /// Read-only view of all addresses, lengths and element sizes of this layout, e.g. to snapshot it in tests.
+    pub const fn describe(&self) -> LayoutReport {
+        LayoutReport {
+            eleven_bit_filters: LayoutRegion {
+                addr: self.eleven_bit_filters_addr,
+                len: self.eleven_bit_filters_len,
+            },
+            twenty_nine_bit_filters: LayoutRegion {
+                addr: self.twenty_nine_bit_filters_addr,
+                len: self.twenty_nine_bit_filters_len,
+            },
+            rx_fifo0: LayoutRegion {
+                addr: self.rx_fifo0_addr,
+                len: self.rx_fifo0_len,
+            },
+            rx_fifo0_data_size: self.rx_fifo0_data_size,
+            rx_fifo1: LayoutRegion {
+                addr: self.rx_fifo1_addr,
+                len: self.rx_fifo1_len,
+            },
+            rx_fifo1_data_size: self.rx_fifo1_data_size,
+            rx_buffers: LayoutRegion {
+                addr: self.rx_buffers_addr,
+                len: self.rx_buffers_len,
+            },
+            rx_buffers_data_size: self.rx_buffers_data_size,
+            tx_event_fifo: LayoutRegion {
+                addr: self.tx_event_fifo_addr,
+                len: self.tx_event_fifo_len,
+            },
+            tx_buffers: LayoutRegion {
+                addr: self.tx_buffers_addr,
+                len: self.tx_buffers_len,
+            },
+            tx_fifo_or_queue_len: self.tx_fifo_or_queue_len,
+            tx_buffers_data_size: self.tx_buffers_data_size,
+            trigger_memory: LayoutRegion {
+                addr: self.trigger_memory_addr,
+                len: self.trigger_memory_len,
+            },
+        }
+    }
+}
+
+/// Start address (in words from the message RAM start) and number of elements of one layout region.
+#[cfg(feature = "h7")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LayoutRegion {
+    pub addr: u16,
+    pub len: u8,
+}
+
+/// Snapshot of a [MessageRamLayout], see [MessageRamLayout::describe].
+#[cfg(feature = "h7")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LayoutReport {
+    pub eleven_bit_filters: LayoutRegion,
+    pub twenty_nine_bit_filters: LayoutRegion,
+    pub rx_fifo0: LayoutRegion,
+    pub rx_fifo0_data_size: DataFieldSize,
+    pub rx_fifo1: LayoutRegion,
+    pub rx_fifo1_data_size: DataFieldSize,
+    pub rx_buffers: LayoutRegion,
+    pub rx_buffers_data_size: DataFieldSize,
+    pub tx_event_fifo: LayoutRegion,
+    /// Dedicated TX buffers, FIFO/Queue elements follow them
+    pub tx_buffers: LayoutRegion,
+    pub tx_fifo_or_queue_len: u8,
+    pub tx_buffers_data_size: DataFieldSize,
+    pub trigger_memory: LayoutRegion,
 }
 
 /// Data size of RX FIFO0/1, RX buffer and TX buffer element, total element size is 8 bytes longer (2 words header).
-/// Should probably be all the same, and either 8 bytes or 64 bytes, unless some very specific configuration is desired.
+/// Should probably be all the same, and either 8 bytes or 64 bytes, unless some very specific configuration is desired,
+/// [MessageRamLayout::require_uniform_data_size] can be used to enforce that.
 #[cfg(feature = "h7")]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum DataFieldSize {
@@ -109,9 +333,25 @@ pub enum DataFieldSize {
 
 #[cfg(feature = "h7")]
 impl DataFieldSize {
-    pub(crate) fn max_len(&self) -> u8 {
+    pub(crate) const fn max_len(&self) -> u8 {
         *self as u8
     }
+
+    /// Smallest data field size that can hold `len` bytes of data, or `None` if `len` is more than 64.
+    /// Useful to size RX and TX elements from the largest expected frame.
+    pub const fn from_data_len(len: usize) -> Option<Self> {
+        match len {
+            0..=8 => Some(DataFieldSize::_8Bytes),
+            9..=12 => Some(DataFieldSize::_12Bytes),
+            13..=16 => Some(DataFieldSize::_16Bytes),
+            17..=20 => Some(DataFieldSize::_20Bytes),
+            21..=24 => Some(DataFieldSize::_24Bytes),
+            25..=32 => Some(DataFieldSize::_32Bytes),
+            33..=48 => Some(DataFieldSize::_48Bytes),
+            49..=64 => Some(DataFieldSize::_64Bytes),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(feature = "h7")]
@@ -141,21 +381,59 @@ impl DataFieldSize {
             DataFieldSize::_64Bytes => 0b111,
         }
     }
+
+    /// Data field words the core assumes for an RXESC/TXESC field value, independently of [Self::words].
+    pub(crate) const fn words_from_config_register(value: u8) -> u16 {
+        match value & 0b111 {
+            0b101 => 8,
+            0b110 => 12,
+            0b111 => 16,
+            v => v as u16 + 2,
+        }
+    }
+
+    /// Whether element stride used by software matches the one hardware is configured with.
+    pub(crate) const fn config_register_consistent(&self) -> bool {
+        Self::words_from_config_register(self.config_register()) == self.words()
+    }
 }
 
+// The element size field value written to RXESC/TXESC must describe the same stride `words()` uses for addressing.
+#[cfg(feature = "h7")]
+const _: () = {
+    let sizes = [
+        DataFieldSize::_8Bytes,
+        DataFieldSize::_12Bytes,
+        DataFieldSize::_16Bytes,
+        DataFieldSize::_20Bytes,
+        DataFieldSize::_24Bytes,
+        DataFieldSize::_32Bytes,
+        DataFieldSize::_48Bytes,
+        DataFieldSize::_64Bytes,
+    ];
+    let mut i = 0;
+    while i < sizes.len() {
+        assert!(sizes[i].config_register_consistent());
+        assert!(sizes[i].words() * 4 == sizes[i].max_len() as u16);
+        i += 1;
+    }
+};
+
 #[cfg(feature = "h7")]
 pub struct MessageRam<'a> {
     layout: &'a MessageRamLayout,
     instance: FdCanInstance,
+    base: MessageRamBase,
 }
 
 #[cfg(not(feature = "h7"))]
 pub struct MessageRam {
     instance: FdCanInstance,
+    base: MessageRamBase,
 }
 
 /// Dedicated TX buffer index that can be obtained during RAM layout by calling allocate_dedicated_tx_buffer().
-/// Not available on G0, G4 and L5, as there is no support for dedicated TX buffers on these MCUs.
+/// G0, G4 and L5 have no dedicated TX buffers, their indices are only returned by transmit_fifo().
 ///
 /// Up to 32 buffers (dedicated or part of FIFO/Queue) could exist, but it depends on the particular peripheral
 /// instance and RAM layout configuration. Contains an instance it belongs to as well, so trying to use an index from one CAN instance
@@ -173,6 +451,9 @@ impl TxBufferIdx {
     }
 }
 
+/// RX FIFO number
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum FIFONr {
     FIFO0,
     FIFO1,
@@ -193,40 +474,254 @@ pub(crate) struct TxBufferElement {
     pub(crate) data: &'static mut [u32],
 }
 
+pub(crate) struct RxBufferElement {
+    pub(crate) r0: Reg<RxBufferElementR0, R>,
+    pub(crate) r1: Reg<RxBufferElementR1, R>,
+    pub(crate) data: &'static [u32],
+}
+
+pub(crate) struct TxEventElement {
+    pub(crate) e0: Reg<TxEventFifoElementE0, R>,
+    pub(crate) e1: Reg<TxEventFifoElementE1, R>,
+}
+
+#[cfg(feature = "h7")]
+pub(crate) struct ExtendedFilterElement {
+    pub(crate) f0: Reg<ExtendedFilterElementF0, RW>,
+    pub(crate) f1: Reg<ExtendedFilterElementF1, RW>,
+}
+
+#[cfg(feature = "h7")]
+pub(crate) struct TriggerElement {
+    pub(crate) t0: Reg<TriggerMemoryElementT0, RW>,
+    pub(crate) t1: Reg<TriggerMemoryElementT1, RW>,
+}
+
 impl TxBufferElement {
     pub(crate) fn fill(&mut self, tx_header: &TxFrameHeader, dlc: Dlc) {
-        self.t0.write(|w| {
-            w.set_esi(tx_header.error_state);
-            w.set_xtd(tx_header.id.into());
-            w.set_rtr(Rtr::TransmitDataFrame); // TODO: support for RTR?
-            w.set_id(tx_header.id.reg_value());
-        });
-        self.t1.write(|w| {
-            w.set_message_marker_low(tx_header.marker.unwrap_or(0)); // TODO: make marker non-optional?
-            w.set_efc(EventFIFOControl::DontStoreTxEvents); // TODO: control TX event store
-            w.set_tsce(TimeStampCaptureEnable::Disabled);
-            w.set_fdf(tx_header.frame_format);
-            w.set_brs(tx_header.bit_rate_switching.into());
-            w.set_dlc(dlc.reg_value());
-            w.set_message_marker_high(0);
-        });
+        let (t0, t1) = Self::encode_tx_header(tx_header, dlc);
+        self.t0.write_value(t0);
+        self.t1.write_value(t1);
+    }
+
+    /// Encodes T0 and T1 words of a TX buffer element, does not touch message RAM.
+    pub(crate) fn encode_tx_header(
+        tx_header: &TxFrameHeader,
+        dlc: Dlc,
+    ) -> (TxBufferElementT0, TxBufferElementT1) {
+        let marker = tx_header.marker.unwrap_or(0); // TODO: make marker non-optional?
+        // Bits 15:8 of T1 are reserved on cores without CCCR.WMM
+        let marker_high = if cfg!(feature = "h7") {
+            (marker >> 8) as u8
+        } else {
+            0
+        };
+        let t0 = TxBufferElementT0::new()
+            .with_esi(tx_header.error_state)
+            .with_xtd(tx_header.id.into())
+            .with_rtr(Rtr::TransmitDataFrame) // TODO: support for RTR?
+            .with_id(tx_header.id.reg_value());
+        let t1 = TxBufferElementT1::new()
+            .with_message_marker_low(marker as u8)
+            .with_efc(match tx_header.marker {
+                Some(_) => EventFIFOControl::StoreTxEvents,
+                None => EventFIFOControl::DontStoreTxEvents,
+            })
+            .with_tsce(if tx_header.timestamp_capture {
+                TimeStampCaptureEnable::Enabled
+            } else {
+                TimeStampCaptureEnable::Disabled
+            })
+            .with_fdf(tx_header.frame_format)
+            .with_brs(tx_header.bit_rate_switching.into())
+            .with_dlc(dlc.reg_value())
+            .with_message_marker_high(marker_high);
+        (t0, t1)
     }
 }
 
 #[cfg(feature = "h7")]
 impl<'a> MessageRam<'a> {
+    /// Dedicated TX buffer element.
     pub(crate) fn tx_buffer(&self, idx: TxBufferIdx) -> Result<TxBufferElement, Error> {
+        if self.layout.is_empty() {
+            return Err(Error::LayoutNotApplied);
+        }
         if self.layout.tx_buffers_len == 0 || idx.idx >= self.layout.tx_buffers_len {
             return Err(Error::TxBufferIndexOutOfRange);
         }
-        let offset = self.layout.tx_buffers_addr + idx.idx as u16;
-        let tx_buffers_len = self.layout.tx_buffers_data_size.words() as usize;
+        Ok(self.tx_element(idx))
+    }
+
+    /// TX FIFO/Queue element, located right after dedicated buffers.
+    pub(crate) fn tx_fifo_buffer(&self, idx: TxBufferIdx) -> Result<TxBufferElement, Error> {
+        if self.layout.is_empty() {
+            return Err(Error::LayoutNotApplied);
+        }
+        let fifo_start = self.layout.tx_buffers_len;
+        let fifo_end = fifo_start + self.layout.tx_fifo_or_queue_len;
+        if idx.idx < fifo_start || idx.idx >= fifo_end {
+            return Err(Error::TxBufferIndexOutOfRange);
+        }
+        Ok(self.tx_element(idx))
+    }
+
+    fn tx_element(&self, idx: TxBufferIdx) -> TxBufferElement {
+        let data_words = self.layout.tx_buffers_data_size.words() as usize;
+        let offset = element_offset(self.layout.tx_buffers_addr, idx.idx, 2 + data_words);
+        debug_assert!(
+            offset + 2 + data_words
+                <= element_offset(
+                    self.layout.tx_buffers_addr,
+                    self.layout.tx_buffers_len + self.layout.tx_fifo_or_queue_len,
+                    2 + data_words,
+                ),
+            "TX element outside of the TX buffers region"
+        );
+        let t0 = self.base.word_ptr(offset);
+        // SAFETY: the index was checked against the TX region of the applied layout, so all `2 + data_words`
+        // words of the element are inside message RAM and owned by this instance.
         unsafe {
-            let tx_buffer_t0 = crate::pac::FDCAN_MSGRAM_ADDR.add(offset as usize);
-            Ok(TxBufferElement {
-                t0: Reg::from_ptr(tx_buffer_t0 as *mut _),
-                t1: Reg::from_ptr(tx_buffer_t0.add(1) as *mut _),
-                data: core::slice::from_raw_parts_mut(tx_buffer_t0.add(2), tx_buffers_len),
+            TxBufferElement {
+                t0: Reg::from_ptr(t0 as *mut _),
+                t1: Reg::from_ptr(t0.add(1) as *mut _),
+                data: core::slice::from_raw_parts_mut(t0.add(2), data_words),
+            }
+        }
+    }
+
+    /// Number of elements in the RX FIFO, get and put indices wrap around at it.
+    pub(crate) fn rx_fifo_len(&self, fifo: FIFONr) -> u8 {
+        match fifo {
+            FIFONr::FIFO0 => self.layout.rx_fifo0_len,
+            FIFONr::FIFO1 => self.layout.rx_fifo1_len,
+        }
+    }
+
+    /// RX FIFO0 or FIFO1 element.
+    pub(crate) fn rx_fifo_element(&self, fifo: FIFONr, idx: u8) -> Result<RxBufferElement, Error> {
+        let (addr, len, data_size) = match fifo {
+            FIFONr::FIFO0 => (
+                self.layout.rx_fifo0_addr,
+                self.layout.rx_fifo0_len,
+                self.layout.rx_fifo0_data_size,
+            ),
+            FIFONr::FIFO1 => (
+                self.layout.rx_fifo1_addr,
+                self.layout.rx_fifo1_len,
+                self.layout.rx_fifo1_data_size,
+            ),
+        };
+        self.rx_element(addr, len, data_size, idx)
+    }
+
+    /// Dedicated RX buffer element.
+    pub(crate) fn rx_buffer_element(&self, idx: u8) -> Result<RxBufferElement, Error> {
+        self.rx_element(
+            self.layout.rx_buffers_addr,
+            self.layout.rx_buffers_len,
+            self.layout.rx_buffers_data_size,
+            idx,
+        )
+    }
+
+    fn rx_element(
+        &self,
+        addr: u16,
+        len: u8,
+        data_size: DataFieldSize,
+        idx: u8,
+    ) -> Result<RxBufferElement, Error> {
+        if idx >= len {
+            return Err(Error::RxBufferIndexOutOfRange);
+        }
+        let data_words = data_size.words() as usize;
+        let r0 = self
+            .base
+            .word_ptr(element_offset(addr, idx, 2 + data_words));
+        // SAFETY: `idx` is below the number of allocated elements, the whole element is inside the region.
+        unsafe {
+            Ok(RxBufferElement {
+                r0: Reg::from_ptr(r0 as *mut _),
+                r1: Reg::from_ptr(r0.add(1) as *mut _),
+                data: core::slice::from_raw_parts(r0.add(2), data_words),
+            })
+        }
+    }
+
+    /// Tx Event FIFO element, two words: E0 (ESI, XTD, RTR, ID) and E1 (MM, ET, FDF, BRS, DLC, TXTS).
+    pub(crate) fn tx_event_element(&self, idx: u8) -> Result<TxEventElement, Error> {
+        if idx >= self.layout.tx_event_fifo_len {
+            return Err(Error::TxEventIndexOutOfRange);
+        }
+        let e0 = self
+            .base
+            .word_ptr(element_offset(self.layout.tx_event_fifo_addr, idx, 2));
+        // SAFETY: `idx` is below the Tx Event FIFO length, both words are inside the region.
+        unsafe {
+            Ok(TxEventElement {
+                e0: Reg::from_ptr(e0 as *mut _),
+                e1: Reg::from_ptr(e0.add(1) as *mut _),
+            })
+        }
+    }
+
+    /// Contiguous run of `len` 11-bit filter elements starting at `start_idx`, one word each.
+    ///
+    /// Elements are handed out as register handles rather than slices, so that runs obtained through different
+    /// `MessageRam`s of one instance do not alias as Rust references.
+    pub(crate) fn standard_filters(
+        &self,
+        start_idx: u8,
+        len: usize,
+    ) -> Result<impl Iterator<Item = Reg<StandardFilterElement, RW>> + use<>, Error> {
+        if start_idx as usize + len > self.layout.eleven_bit_filters_len as usize {
+            return Err(Error::FilterIndexOutOfRange);
+        }
+        let start = self.base.word_ptr(element_offset(
+            self.layout.eleven_bit_filters_addr,
+            start_idx,
+            1,
+        ));
+        // SAFETY: the run was checked to end within the 11-bit filter region.
+        Ok((0..len).map(move |i| unsafe { Reg::from_ptr(start.add(i) as *mut _) }))
+    }
+
+    /// Contiguous run of `len` 29-bit filter elements starting at `start_idx`, two words each.
+    pub(crate) fn extended_filters(
+        &self,
+        start_idx: u8,
+        len: usize,
+    ) -> Result<impl Iterator<Item = ExtendedFilterElement> + use<>, Error> {
+        if start_idx as usize + len > self.layout.twenty_nine_bit_filters_len as usize {
+            return Err(Error::FilterIndexOutOfRange);
+        }
+        let start = self.base.word_ptr(element_offset(
+            self.layout.twenty_nine_bit_filters_addr,
+            start_idx,
+            2,
+        ));
+        // SAFETY: the run was checked to end within the 29-bit filter region.
+        Ok((0..len).map(move |i| unsafe {
+            ExtendedFilterElement {
+                f0: Reg::from_ptr(start.add(2 * i) as *mut _),
+                f1: Reg::from_ptr(start.add(2 * i + 1) as *mut _),
+            }
+        }))
+    }
+
+    pub(crate) fn trigger_element(&self, idx: u8) -> Result<TriggerElement, Error> {
+        if idx >= self.layout.trigger_memory_len {
+            return Err(Error::TriggerIndexOutOfRange);
+        }
+        let t0 = self
+            .base
+            .word_ptr(element_offset(self.layout.trigger_memory_addr, idx, 2));
+        // SAFETY: `idx` is below the trigger memory length, both words are inside the region.
+        unsafe {
+            Ok(TriggerElement {
+                t0: Reg::from_ptr(t0 as *mut _),
+                t1: Reg::from_ptr(t0.add(1) as *mut _),
             })
         }
     }
@@ -235,22 +730,434 @@ impl<'a> MessageRam<'a> {
     // pub(crate) tx_queue_put()
 }
 
+/// Word offset of element `idx` in a region starting at word `addr`, each element being `element_words` long.
+const fn element_offset(addr: u16, idx: u8, element_words: usize) -> usize {
+    addr as usize + idx as usize * element_words
+}
+
+/// Start of the message RAM shared by all instances. Always the peripheral's message RAM, except for host tests
+/// which point it at a plain buffer.
+#[derive(Copy, Clone)]
+pub(crate) struct MessageRamBase(*mut u32);
+
+// SAFETY: only an address, accesses through it are synchronized by the owning `FdCan` like register accesses.
+unsafe impl Send for MessageRamBase {}
+unsafe impl Sync for MessageRamBase {}
+
+impl MessageRamBase {
+    pub(crate) const PERIPHERAL: Self = Self(crate::pac::FDCAN_MSGRAM_ADDR);
+
+    /// Message RAM backed by `words` instead of the peripheral, must be at least `FDCAN_MSGRAM_LEN_WORDS` long.
+    #[cfg(test)]
+    pub(crate) fn host(words: &'static mut [u32]) -> Self {
+        assert!(words.len() >= crate::pac::FDCAN_MSGRAM_LEN_WORDS);
+        Self(words.as_mut_ptr())
+    }
+
+    /// Pointer to message RAM word `offset`, computing it is safe, dereferencing is up to the caller.
+    pub(crate) fn word_ptr(self, offset: usize) -> *mut u32 {
+        debug_assert!(offset < crate::pac::FDCAN_MSGRAM_LEN_WORDS);
+        self.0.wrapping_add(offset)
+    }
+}
+
+// Fixed layout regions must be contiguous, the TX elements must end exactly at the instance section boundary,
+// and both sections must fit into message RAM.
 #[cfg(not(feature = "h7"))]
-impl MessageRam {}
+const _: () = {
+    use crate::pac::fixed_layout::{
+        INSTANCE_WORDS, RX_ELEMENT_WORDS, RX_FIFO0_ADDR, RX_FIFO1_ADDR, TX_BUFFERS_ADDR,
+        TX_ELEMENT_WORDS, TX_EVENT_FIFO_ADDR,
+    };
+    use crate::pac::limits::{RX_FIFO, TX_EVENT_FIFO};
+    // Regions follow each other without gaps: RX FIFO0, RX FIFO1, Tx events, TX buffers
+    assert!(element_offset(RX_FIFO0_ADDR, RX_FIFO, RX_ELEMENT_WORDS) == RX_FIFO1_ADDR as usize);
+    assert!(
+        element_offset(RX_FIFO1_ADDR, RX_FIFO, RX_ELEMENT_WORDS) == TX_EVENT_FIFO_ADDR as usize
+    );
+    assert!(element_offset(TX_EVENT_FIFO_ADDR, TX_EVENT_FIFO, 2) == TX_BUFFERS_ADDR as usize);
+    let tx_end = element_offset(
+        TX_BUFFERS_ADDR,
+        crate::pac::limits::TX_BUFFERS,
+        TX_ELEMENT_WORDS,
+    );
+    assert!(tx_end == INSTANCE_WORDS as usize);
+    assert!(2 * INSTANCE_WORDS as usize <= crate::pac::FDCAN_MSGRAM_LEN_WORDS);
+};
+
+#[cfg(not(feature = "h7"))]
+impl MessageRam {
+    /// TX FIFO/Queue element. The layout is fixed in hardware: there are no dedicated TX buffers, all elements
+    /// belong to the FIFO/Queue and hold up to 64 data bytes.
+    pub(crate) fn tx_fifo_buffer(&self, idx: TxBufferIdx) -> Result<TxBufferElement, Error> {
+        use crate::pac::fixed_layout::{TX_BUFFERS_ADDR, TX_ELEMENT_WORDS};
+
+        if idx.idx >= crate::pac::limits::TX_BUFFERS {
+            return Err(Error::TxBufferIndexOutOfRange);
+        }
+        let offset = element_offset(self.section() + TX_BUFFERS_ADDR, idx.idx, TX_ELEMENT_WORDS);
+        let t0 = self.base.word_ptr(offset);
+        // SAFETY: `idx` is below the number of TX elements, the whole element is inside this instance's section.
+        unsafe {
+            Ok(TxBufferElement {
+                t0: Reg::from_ptr(t0 as *mut _),
+                t1: Reg::from_ptr(t0.add(1) as *mut _),
+                data: core::slice::from_raw_parts_mut(t0.add(2), TX_ELEMENT_WORDS - 2),
+            })
+        }
+    }
+
+    /// Number of elements in the RX FIFO, get and put indices wrap around at it.
+    pub(crate) fn rx_fifo_len(&self, _fifo: FIFONr) -> u8 {
+        crate::pac::limits::RX_FIFO
+    }
+
+    /// RX FIFO0 or FIFO1 element, holding up to 64 data bytes.
+    pub(crate) fn rx_fifo_element(&self, fifo: FIFONr, idx: u8) -> Result<RxBufferElement, Error> {
+        use crate::pac::fixed_layout::{RX_ELEMENT_WORDS, RX_FIFO0_ADDR, RX_FIFO1_ADDR};
+
+        if idx >= crate::pac::limits::RX_FIFO {
+            return Err(Error::RxBufferIndexOutOfRange);
+        }
+        let addr = match fifo {
+            FIFONr::FIFO0 => RX_FIFO0_ADDR,
+            FIFONr::FIFO1 => RX_FIFO1_ADDR,
+        };
+        let r0 = self
+            .base
+            .word_ptr(element_offset(self.section() + addr, idx, RX_ELEMENT_WORDS));
+        // SAFETY: `idx` is below the FIFO length, the whole element is inside this instance's section.
+        unsafe {
+            Ok(RxBufferElement {
+                r0: Reg::from_ptr(r0 as *mut _),
+                r1: Reg::from_ptr(r0.add(1) as *mut _),
+                data: core::slice::from_raw_parts(r0.add(2), RX_ELEMENT_WORDS - 2),
+            })
+        }
+    }
+
+    /// Tx Event FIFO element, two words: E0 (ESI, XTD, RTR, ID) and E1 (MM, ET, FDF, BRS, DLC, TXTS).
+    pub(crate) fn tx_event_element(&self, idx: u8) -> Result<TxEventElement, Error> {
+        if idx >= crate::pac::limits::TX_EVENT_FIFO {
+            return Err(Error::TxEventIndexOutOfRange);
+        }
+        let addr = self.section() + crate::pac::fixed_layout::TX_EVENT_FIFO_ADDR;
+        let e0 = self.base.word_ptr(element_offset(addr, idx, 2));
+        // SAFETY: `idx` is below the Tx Event FIFO length, both words are inside this instance's section.
+        unsafe {
+            Ok(TxEventElement {
+                e0: Reg::from_ptr(e0 as *mut _),
+                e1: Reg::from_ptr(e0.add(1) as *mut _),
+            })
+        }
+    }
+
+    /// First word of this instance's section.
+    const fn section(&self) -> u16 {
+        match self.instance {
+            FdCanInstance::FdCan1 => 0,
+            FdCanInstance::FdCan2 => crate::pac::fixed_layout::INSTANCE_WORDS,
+        }
+    }
+}
 
 impl<M> FdCan<M> {
+    /// Largest data length that fits into any of the allocated RX or TX elements of the current layout,
+    /// useful to size frame buffers without keeping the layout around.
     #[cfg(feature = "h7")]
-    pub(crate) fn message_ram(&mut self) -> MessageRam<'_> {
+    pub fn max_frame_len(&self) -> u8 {
+        let l = &self.config.layout;
+        [
+            (l.rx_fifo0_len, l.rx_fifo0_data_size),
+            (l.rx_fifo1_len, l.rx_fifo1_data_size),
+            (l.rx_buffers_len, l.rx_buffers_data_size),
+            (
+                l.tx_buffers_len + l.tx_fifo_or_queue_len,
+                l.tx_buffers_data_size,
+            ),
+        ]
+        .iter()
+        .filter(|(len, _)| *len > 0)
+        .map(|(_, size)| size.max_len())
+        .max()
+        .unwrap_or(0)
+    }
+
+    /// Message RAM layout currently applied to this instance.
+    #[cfg(feature = "h7")]
+    #[inline]
+    pub fn layout(&self) -> &MessageRamLayout {
+        &self.config.layout
+    }
+
+    #[cfg(feature = "h7")]
+    pub(crate) fn message_ram(&self) -> MessageRam<'_> {
         MessageRam {
             layout: &self.config.layout,
             instance: self.instance,
+            base: self.msg_ram,
         }
     }
 
     #[cfg(not(feature = "h7"))]
-    pub(crate) fn message_ram(&mut self) -> MessageRam {
+    pub(crate) fn message_ram(&self) -> MessageRam {
         MessageRam {
             instance: self.instance,
+            base: self.msg_ram,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    extern crate std;
+
+    use super::*;
+    #[cfg(feature = "h7")]
+    use crate::message_ram_builder::{MessageRamBuilder, RamBuilderInitialState};
+    use std::boxed::Box;
+
+    /// Zeroed host buffer standing in for the whole message RAM, leaked to live as long as the test.
+    pub(crate) fn host_ram() -> MessageRamBase {
+        let words = std::vec![0u32; crate::pac::FDCAN_MSGRAM_LEN_WORDS];
+        MessageRamBase::host(Box::leak(words.into_boxed_slice()))
+    }
+
+    /// Layout allocated with `builder`: `filters` standard filters, an RX FIFO0 of `rx_fifo` and a TX FIFO of
+    /// `tx_fifo` 8 byte elements and `triggers` trigger elements, all other sections empty.
+    #[cfg(feature = "h7")]
+    pub(crate) fn fifo_layout(
+        builder: MessageRamBuilder<RamBuilderInitialState>,
+        filters: u8,
+        rx_fifo: u8,
+        tx_fifo: u8,
+        triggers: u8,
+    ) -> (MessageRamLayout, MessageRamBuilder<RamBuilderInitialState>) {
+        builder
+            .allocate_11bit_filters(filters)
+            .unwrap()
+            .allocate_29bit_filters(0)
+            .unwrap()
+            .allocate_rx_fifo0_buffers(rx_fifo, DataFieldSize::_8Bytes)
+            .unwrap()
+            .allocate_rx_fifo1_buffers(0, DataFieldSize::_8Bytes)
+            .unwrap()
+            .skip_dedicated_buffers()
+            .allocate_tx_event_fifo_buffers(0)
+            .unwrap()
+            .tx_buffer_element_size(DataFieldSize::_8Bytes)
+            .allocate_fifo_or_queue(tx_fifo)
+            .unwrap()
+            .allocate_triggers(triggers)
+            .unwrap()
+    }
+
+    /// Layout with three dedicated 64 byte TX buffers followed by a two element TX FIFO, starting at word 0x10.
+    #[cfg(feature = "h7")]
+    pub(crate) fn tx_layout(instance: FdCanInstance) -> (MessageRamLayout, [TxBufferIdx; 3]) {
+        let b = MessageRamBuilder::for_region(0x10, 0x400, instance)
+            .allocate_11bit_filters(0)
+            .unwrap()
+            .allocate_29bit_filters(0)
+            .unwrap()
+            .allocate_rx_fifo0_buffers(1, DataFieldSize::_8Bytes)
+            .unwrap()
+            .allocate_rx_fifo1_buffers(0, DataFieldSize::_8Bytes)
+            .unwrap()
+            .skip_dedicated_buffers()
+            .allocate_tx_event_fifo_buffers(0)
+            .unwrap()
+            .tx_buffer_element_size(DataFieldSize::_64Bytes);
+        let (first, b) = b.allocate_dedicated_tx_buffer().unwrap();
+        let (second, b) = b.allocate_dedicated_tx_buffer().unwrap();
+        let (third, b) = b.allocate_dedicated_tx_buffer().unwrap();
+        let (layout, _) = b
+            .allocate_fifo_or_queue(2)
+            .unwrap()
+            .allocate_triggers(0)
+            .unwrap();
+        (layout, [first, second, third])
+    }
+
+    /// Distance in words between the T0 words of two TX elements.
+    fn tx_stride(first: &TxBufferElement, second: &TxBufferElement) -> usize {
+        (second.t0.as_ptr() as usize - first.t0.as_ptr() as usize) / 4
+    }
+
+    #[test]
+    fn tx_element_id_placement() {
+        use crate::pac::message_ram::Xtd;
+        use crate::{ExtendedId, Id, StandardId};
+
+        // Standard ID must end up in bits 28:18 of T0 and extended ID in bits 28:0 with XTD set, otherwise every
+        // frame goes out with a wrong arbitration ID.
+        let standard = TxBufferElementT0::new()
+            .with_xtd(Xtd::ElevenBits)
+            .with_id(Id::Standard(StandardId::new(0x123).unwrap()).reg_value());
+        assert_eq!(standard.into_bits(), 0x123 << 18);
+        let extended = TxBufferElementT0::new()
+            .with_xtd(Xtd::TwentyNineBits)
+            .with_id(Id::Extended(ExtendedId::MAX).reg_value());
+        assert_eq!(extended.into_bits(), (1 << 30) | 0x1FFF_FFFF);
+    }
+
+    #[test]
+    fn rx_element_r1_field_positions() {
+        // R1 is decoded through the bitfield, make sure FIDX and ANMF did not swap places with the reserved bits
+        let r1 = RxBufferElementR1::from_bits((1 << 31) | (0x45 << 24) | (0xF << 16) | 0x1234);
+        assert!(r1.anmf());
+        assert_eq!(r1.fidx(), 0x45);
+        assert_eq!(r1.dlc(), 0xF);
+        assert_eq!(r1.rxts(), 0x1234);
+    }
+
+    #[cfg(feature = "h7")]
+    #[test]
+    fn dedicated_tx_buffers_do_not_alias() {
+        let (layout, buffers) = tx_layout(FdCanInstance::FdCan1);
+        let ram = MessageRam {
+            layout: &layout,
+            instance: FdCanInstance::FdCan1,
+            base: host_ram(),
+        };
+        for (n, idx) in buffers.iter().enumerate() {
+            let element = ram.tx_buffer(*idx).unwrap();
+            element
+                .t0
+                .write_value(TxBufferElementT0::from_bits(0x100 + n as u32));
+            element
+                .t1
+                .write_value(TxBufferElementT1::from_bits(0x200 + n as u32));
+            element.data.fill(0x1111_1111 * (n as u32 + 1));
+        }
+        for (n, idx) in buffers.iter().enumerate() {
+            let element = ram.tx_buffer(*idx).unwrap();
+            assert_eq!(element.t0.read().into_bits(), 0x100 + n as u32);
+            assert_eq!(element.t1.read().into_bits(), 0x200 + n as u32);
+            assert_eq!(element.data.len(), 16);
+            assert!(
+                element
+                    .data
+                    .iter()
+                    .all(|w| *w == 0x1111_1111 * (n as u32 + 1))
+            );
+        }
+        // Each element is T0, T1 and 16 data words long
+        let t0 = |n: usize| ram.tx_buffer(buffers[n]).unwrap().t0.as_ptr() as usize;
+        assert_eq!(t0(1) - t0(0), 18 * 4);
+        assert_eq!(t0(2) - t0(1), 18 * 4);
+    }
+
+    #[cfg(feature = "h7")]
+    #[test]
+    fn tx_fifo_elements_follow_dedicated_buffers() {
+        let (layout, buffers) = tx_layout(FdCanInstance::FdCan1);
+        let ram = MessageRam {
+            layout: &layout,
+            instance: FdCanInstance::FdCan1,
+            base: host_ram(),
+        };
+        let fifo = |idx| TxBufferIdx {
+            instance: FdCanInstance::FdCan1,
+            idx,
+        };
+        let last_dedicated = ram.tx_buffer(buffers[2]).unwrap();
+        let first_fifo = ram.tx_fifo_buffer(fifo(3)).unwrap();
+        let second_fifo = ram.tx_fifo_buffer(fifo(4)).unwrap();
+        assert_eq!(tx_stride(&last_dedicated, &first_fifo), 18);
+        assert_eq!(tx_stride(&first_fifo, &second_fifo), 18);
+        assert!(matches!(
+            ram.tx_fifo_buffer(fifo(5)),
+            Err(Error::TxBufferIndexOutOfRange)
+        ));
+        assert!(matches!(
+            ram.tx_fifo_buffer(buffers[0]),
+            Err(Error::TxBufferIndexOutOfRange)
+        ));
+    }
+
+    #[cfg(feature = "h7")]
+    #[test]
+    fn relayout_keeps_staged_frames_of_other_instances() {
+        use crate::fdcan::tests::host_fdcan;
+        use crate::fdcan::{ConfigMode, NormalOperationMode};
+        use crate::message_ram_builder::{basic_layout, basic_layout_classic};
+        use crate::{StandardId, TxFrameHeader};
+
+        let base = host_ram();
+        let (first, [staged, ..]) = tx_layout(FdCanInstance::FdCan1);
+        let b = MessageRamBuilder::for_region(first.region().1, 0x400, FdCanInstance::FdCan2);
+        let (second, b) = basic_layout_classic(b).unwrap();
+        let (third, _) = basic_layout(b).unwrap();
+
+        let mut can1 = host_fdcan::<NormalOperationMode>(FdCanInstance::FdCan1, base);
+        can1.config.layout = first;
+        let header = TxFrameHeader::new(StandardId::new(0x123).unwrap().into());
+        can1.write_tx_buffer(staged, header, &[1, 2, 3, 4, 5, 6, 7, 8])
+            .unwrap();
+        let mut can3 = host_fdcan::<ConfigMode>(FdCanInstance::FdCan3, base);
+        can3.set_layout(third);
+        can3.init_ram_region(0xDEAD_BEEF).unwrap();
+        let words = |(start, end): (u16, u16)| {
+            (start..end)
+                // SAFETY: host RAM covers every layout offset.
+                .map(|offset| unsafe { *base.word_ptr(offset as usize) })
+                .collect::<std::vec::Vec<_>>()
+        };
+        let before = (words(first.region()), words(third.region()));
+
+        let mut can2 = host_fdcan::<ConfigMode>(FdCanInstance::FdCan2, base);
+        assert!(matches!(
+            can2.clear_layout_ram(),
+            Err(Error::LayoutNotApplied)
+        ));
+        can2.set_layout(second);
+        can2.init_ram_region(0xFFFF_FFFF).unwrap();
+        let (relaid, _) = fifo_layout(second.relayout(FdCanInstance::FdCan2).unwrap(), 4, 2, 1, 0);
+        can2.clear_layout_ram().unwrap();
+        can2.set_layout(relaid);
+
+        assert!(words(second.region()).iter().all(|w| *w == 0));
+        assert_eq!((words(first.region()), words(third.region())), before);
+        let element = can1.message_ram().tx_buffer(staged).unwrap();
+        assert_eq!(element.t0.read().id(), 0x123 << 18);
+        assert_eq!(element.data[0], u32::from_le_bytes([1, 2, 3, 4]));
+    }
+
+    #[cfg(feature = "h7")]
+    #[test]
+    fn empty_layout_cannot_be_relaid() {
+        assert!(matches!(
+            MessageRamLayout::default().relayout(FdCanInstance::FdCan1),
+            Err(crate::message_ram_builder::MessageRamBuilderError::LayoutNotApplied)
+        ));
+    }
+
+    #[cfg(not(feature = "h7"))]
+    #[test]
+    fn fixed_layout_tx_elements_do_not_alias() {
+        use crate::pac::fixed_layout::{INSTANCE_WORDS, TX_BUFFERS_ADDR, TX_ELEMENT_WORDS};
+
+        let base = host_ram();
+        let start = base.word_ptr(0) as usize;
+        for (instance, section) in [
+            (FdCanInstance::FdCan1, 0),
+            (FdCanInstance::FdCan2, INSTANCE_WORDS as usize),
+        ] {
+            let ram = MessageRam { instance, base };
+            let element = |idx| ram.tx_fifo_buffer(TxBufferIdx { instance, idx }).unwrap();
+            let first = element(0);
+            assert_eq!(
+                (first.t0.as_ptr() as usize - start) / 4,
+                section + TX_BUFFERS_ADDR as usize
+            );
+            assert_eq!(first.data.len(), TX_ELEMENT_WORDS - 2);
+            for idx in 1..crate::pac::limits::TX_BUFFERS {
+                assert_eq!(
+                    tx_stride(&element(idx - 1), &element(idx)),
+                    TX_ELEMENT_WORDS
+                );
+            }
         }
     }
 }
@@ -0,0 +1,253 @@
+#![allow(clippy::missing_safety_doc)]
+#![allow(clippy::identity_op)]
+#![allow(clippy::unnecessary_cast)]
+#![allow(clippy::erasing_op)]
+
+#[doc = "FDCAN Clock Calibration Unit"]
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct Ccu {
+    ptr: *mut u8,
+}
+unsafe impl Send for Ccu {}
+unsafe impl Sync for Ccu {}
+impl Ccu {
+    #[inline(always)]
+    pub const unsafe fn from_ptr(ptr: *mut ()) -> Self {
+        Self { ptr: ptr as _ }
+    }
+    #[inline(always)]
+    pub const fn as_ptr(&self) -> *mut () {
+        self.ptr as _
+    }
+    #[doc = "Clock Calibration Unit Core Release Register"]
+    #[inline(always)]
+    pub const fn crel(self) -> crate::pac_traits::Reg<u32, crate::pac_traits::R> {
+        unsafe { crate::pac_traits::Reg::from_ptr(self.ptr.add(0x0usize) as _) }
+    }
+    #[doc = "Calibration Configuration Register"]
+    #[inline(always)]
+    pub const fn ccfg(self) -> crate::pac_traits::Reg<regs::Ccfg, crate::pac_traits::RW> {
+        unsafe { crate::pac_traits::Reg::from_ptr(self.ptr.add(0x04usize) as _) }
+    }
+    #[doc = "Calibration Status Register"]
+    #[inline(always)]
+    pub const fn cstat(self) -> crate::pac_traits::Reg<regs::Cstat, crate::pac_traits::R> {
+        unsafe { crate::pac_traits::Reg::from_ptr(self.ptr.add(0x08usize) as _) }
+    }
+    #[doc = "Clock Calibration Unit Interrupt Register"]
+    #[inline(always)]
+    pub const fn ir(self) -> crate::pac_traits::Reg<regs::Ir, crate::pac_traits::RW> {
+        unsafe { crate::pac_traits::Reg::from_ptr(self.ptr.add(0x10usize) as _) }
+    }
+}
+pub mod regs {
+    #[doc = "Calibration Configuration Register"]
+    #[repr(transparent)]
+    #[derive(Copy, Clone, Eq, PartialEq)]
+    pub struct Ccfg(pub u32);
+    impl Ccfg {
+        #[doc = "Time Quanta per Bit Time"]
+        #[inline(always)]
+        pub const fn tqbt(&self) -> u8 {
+            let val = (self.0 >> 0usize) & 0x1f;
+            val as u8
+        }
+        #[doc = "Time Quanta per Bit Time"]
+        #[inline(always)]
+        pub fn set_tqbt(&mut self, val: u8) {
+            self.0 = (self.0 & !(0x1f << 0usize)) | (((val as u32) & 0x1f) << 0usize);
+        }
+        #[doc = "Bypass Clock Calibration"]
+        #[inline(always)]
+        pub const fn bcc(&self) -> bool {
+            let val = (self.0 >> 6usize) & 0x01;
+            val != 0
+        }
+        #[doc = "Bypass Clock Calibration"]
+        #[inline(always)]
+        pub fn set_bcc(&mut self, val: bool) {
+            self.0 = (self.0 & !(0x01 << 6usize)) | (((val as u32) & 0x01) << 6usize);
+        }
+        #[doc = "Configuration Frame Length"]
+        #[inline(always)]
+        pub const fn cfl(&self) -> bool {
+            let val = (self.0 >> 7usize) & 0x01;
+            val != 0
+        }
+        #[doc = "Configuration Frame Length"]
+        #[inline(always)]
+        pub fn set_cfl(&mut self, val: bool) {
+            self.0 = (self.0 & !(0x01 << 7usize)) | (((val as u32) & 0x01) << 7usize);
+        }
+        #[doc = "Oscillator Clock Periods Minimum"]
+        #[inline(always)]
+        pub const fn ocpm(&self) -> u8 {
+            let val = (self.0 >> 8usize) & 0xff;
+            val as u8
+        }
+        #[doc = "Oscillator Clock Periods Minimum"]
+        #[inline(always)]
+        pub fn set_ocpm(&mut self, val: u8) {
+            self.0 = (self.0 & !(0xff << 8usize)) | (((val as u32) & 0xff) << 8usize);
+        }
+        #[doc = "Clock Divider"]
+        #[inline(always)]
+        pub const fn cdiv(&self) -> u8 {
+            let val = (self.0 >> 16usize) & 0x0f;
+            val as u8
+        }
+        #[doc = "Clock Divider"]
+        #[inline(always)]
+        pub fn set_cdiv(&mut self, val: u8) {
+            self.0 = (self.0 & !(0x0f << 16usize)) | (((val as u32) & 0x0f) << 16usize);
+        }
+        #[doc = "Software Reset"]
+        #[inline(always)]
+        pub const fn swr(&self) -> bool {
+            let val = (self.0 >> 31usize) & 0x01;
+            val != 0
+        }
+        #[doc = "Software Reset"]
+        #[inline(always)]
+        pub fn set_swr(&mut self, val: bool) {
+            self.0 = (self.0 & !(0x01 << 31usize)) | (((val as u32) & 0x01) << 31usize);
+        }
+    }
+    impl Default for Ccfg {
+        #[inline(always)]
+        fn default() -> Ccfg {
+            Ccfg(0)
+        }
+    }
+    impl core::fmt::Debug for Ccfg {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            f.debug_struct("Ccfg")
+                .field("tqbt", &self.tqbt())
+                .field("bcc", &self.bcc())
+                .field("cfl", &self.cfl())
+                .field("ocpm", &self.ocpm())
+                .field("cdiv", &self.cdiv())
+                .field("swr", &self.swr())
+                .finish()
+        }
+    }
+    #[cfg(feature = "defmt")]
+    impl defmt::Format for Ccfg {
+        fn format(&self, f: defmt::Formatter) {
+            defmt::write!(
+                f,
+                "Ccfg {{ tqbt: {=u8:?}, bcc: {=bool:?}, cfl: {=bool:?}, ocpm: {=u8:?}, cdiv: {=u8:?}, swr: {=bool:?} }}",
+                self.tqbt(),
+                self.bcc(),
+                self.cfl(),
+                self.ocpm(),
+                self.cdiv(),
+                self.swr()
+            )
+        }
+    }
+    #[doc = "Calibration Status Register"]
+    #[repr(transparent)]
+    #[derive(Copy, Clone, Eq, PartialEq)]
+    pub struct Cstat(pub u32);
+    impl Cstat {
+        #[doc = "Oscillator Clock Period Counter"]
+        #[inline(always)]
+        pub const fn ocpc(&self) -> u32 {
+            let val = (self.0 >> 0usize) & 0x0003_ffff;
+            val as u32
+        }
+        #[doc = "Time Quanta Counter"]
+        #[inline(always)]
+        pub const fn tqc(&self) -> u16 {
+            let val = (self.0 >> 18usize) & 0x07ff;
+            val as u16
+        }
+        #[doc = "Calibration State"]
+        #[inline(always)]
+        pub const fn cals(&self) -> u8 {
+            let val = (self.0 >> 30usize) & 0x03;
+            val as u8
+        }
+    }
+    impl Default for Cstat {
+        #[inline(always)]
+        fn default() -> Cstat {
+            Cstat(0)
+        }
+    }
+    impl core::fmt::Debug for Cstat {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            f.debug_struct("Cstat")
+                .field("ocpc", &self.ocpc())
+                .field("tqc", &self.tqc())
+                .field("cals", &self.cals())
+                .finish()
+        }
+    }
+    #[cfg(feature = "defmt")]
+    impl defmt::Format for Cstat {
+        fn format(&self, f: defmt::Formatter) {
+            defmt::write!(
+                f,
+                "Cstat {{ ocpc: {=u32:?}, tqc: {=u16:?}, cals: {=u8:?} }}",
+                self.ocpc(),
+                self.tqc(),
+                self.cals()
+            )
+        }
+    }
+    #[doc = "Clock Calibration Unit Interrupt Register"]
+    #[repr(transparent)]
+    #[derive(Copy, Clone, Eq, PartialEq)]
+    pub struct Ir(pub u32);
+    impl Ir {
+        #[doc = "Calibration Watchdog Event"]
+        #[inline(always)]
+        pub const fn cwe(&self) -> bool {
+            let val = (self.0 >> 0usize) & 0x01;
+            val != 0
+        }
+        #[doc = "Calibration Watchdog Event"]
+        #[inline(always)]
+        pub fn set_cwe(&mut self, val: bool) {
+            self.0 = (self.0 & !(0x01 << 0usize)) | (((val as u32) & 0x01) << 0usize);
+        }
+        #[doc = "Calibration State Changed"]
+        #[inline(always)]
+        pub const fn csc(&self) -> bool {
+            let val = (self.0 >> 1usize) & 0x01;
+            val != 0
+        }
+        #[doc = "Calibration State Changed"]
+        #[inline(always)]
+        pub fn set_csc(&mut self, val: bool) {
+            self.0 = (self.0 & !(0x01 << 1usize)) | (((val as u32) & 0x01) << 1usize);
+        }
+    }
+    impl Default for Ir {
+        #[inline(always)]
+        fn default() -> Ir {
+            Ir(0)
+        }
+    }
+    impl core::fmt::Debug for Ir {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            f.debug_struct("Ir")
+                .field("cwe", &self.cwe())
+                .field("csc", &self.csc())
+                .finish()
+        }
+    }
+    #[cfg(feature = "defmt")]
+    impl defmt::Format for Ir {
+        fn format(&self, f: defmt::Formatter) {
+            defmt::write!(
+                f,
+                "Ir {{ cwe: {=bool:?}, csc: {=bool:?} }}",
+                self.cwe(),
+                self.csc()
+            )
+        }
+    }
+}
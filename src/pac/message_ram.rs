@@ -93,6 +93,7 @@ pub struct StandardFilterElement {
     pub sfid2: u16,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum StandardFilterType {
@@ -122,6 +123,7 @@ impl StandardFilterType {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum StandardFilterConfiguration {
@@ -143,6 +145,9 @@ pub enum StandardFilterConfiguration {
     StoreAsDebugMessage = 0b111,
 }
 
+/// Extended Filter Element Configuration (EFEC) uses the same encoding as SFEC.
+pub type ExtendedFilterConfiguration = StandardFilterConfiguration;
+
 impl StandardFilterConfiguration {
     const fn into_bits(self) -> u8 {
         self as u8
@@ -163,6 +168,74 @@ impl StandardFilterConfiguration {
     }
 }
 
+/// Up to 64 filter elements can be configured for 29-bit IDs. When accessing an Extended Message ID
+/// Filter element, its address is the Filter List Extended Start Address XIDFC.FLESA plus two times
+/// the index of the filter element (0…63). This is the first word (F0) of the element.
+#[bitfield(u32, order = Msb, debug = false, defmt = cfg(feature = "defmt"))]
+pub struct ExtendedFilterElementF0 {
+    /// Extended Filter Element Configuration
+    ///
+    /// All enabled filter elements are used for acceptance filtering of 29-bit ID frames, encoding is
+    /// the same as for SFEC.
+    #[bits(3)]
+    pub efec: ExtendedFilterConfiguration,
+
+    /// Extended Filter ID 1
+    ///
+    /// First ID of extended ID filter element. When filtering for Rx Buffers, Sync messages, or for debug
+    /// messages this field defines the ID of the message to be stored.
+    #[bits(29)]
+    pub efid1: u32,
+}
+
+/// Second word (F1) of an Extended Message ID Filter element.
+#[bitfield(u32, order = Msb, debug = false, defmt = cfg(feature = "defmt"))]
+pub struct ExtendedFilterElementF1 {
+    /// Extended Filter Type
+    #[bits(2)]
+    pub eft: ExtendedFilterType,
+
+    #[bits(1)]
+    _reserved: u8,
+
+    /// Extended Filter ID 2
+    ///
+    /// Second ID of extended ID filter element, mask for classic filters or Rx Buffer / debug message
+    /// configuration if EFEC = "111", see SFID2.
+    #[bits(29)]
+    pub efid2: u32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum ExtendedFilterType {
+    /// Range filter from EFID1 to EFID2 (EFID2 ≥ EFID1), XIDAM mask applied
+    Range = 0b00,
+    /// Dual ID filter for EFID1 or EFID2
+    DualID = 0b01,
+    /// Classic filter: EFID1 = filter, EFID2 = mask
+    Classic = 0b10,
+    /// Range filter from EFID1 to EFID2 (EFID2 ≥ EFID1), XIDAM mask not applied
+    RangeNoMask = 0b11,
+}
+
+impl ExtendedFilterType {
+    const fn into_bits(self) -> u8 {
+        self as u8
+    }
+
+    const fn from_bits(value: u8) -> ExtendedFilterType {
+        match value {
+            0b00 => ExtendedFilterType::Range,
+            0b01 => ExtendedFilterType::DualID,
+            0b10 => ExtendedFilterType::Classic,
+            0b11 => ExtendedFilterType::RangeNoMask,
+            _ => unreachable!(),
+        }
+    }
+}
+
 /// The Tx Buffers section can be configured to hold dedicated Tx Buffers as well as a Tx FIFO / Tx Queue.
 ///
 /// In case that the Tx Buffers section is shared by dedicated Tx buffers and a Tx FIFO / Tx Queue:
@@ -259,3 +332,246 @@ impl From<bool> for BitRateSwitch {
         }
     }
 }
+
+/// First word of an Rx Buffer or Rx FIFO element.
+///
+/// Rx Buffers and both Rx FIFOs share the element format, the element size is configured via RXESC.
+#[bitfield(u32, order = Msb, debug = false, defmt = cfg(feature = "defmt"))]
+pub struct RxBufferElementR0 {
+    /// Error State Indicator of the transmitting node, only meaningful for FD frames
+    #[bits(1)]
+    pub esi: Esi,
+
+    /// Extended Identifier
+    #[bits(1)]
+    pub xtd: Xtd,
+
+    /// Remote Transmission Request, always 0 for FD frames
+    #[bits(1)]
+    pub rtr: Rtr,
+
+    /// Standard or extended identifier depending on bit XTD. A standard identifier is stored into ID 28:18.
+    #[bits(29)]
+    pub id: u32,
+}
+
+/// Second word of an Rx Buffer or Rx FIFO element.
+#[bitfield(u32, order = Msb, debug = false, defmt = cfg(feature = "defmt"))]
+pub struct RxBufferElementR1 {
+    /// Accepted Non-matching Frame
+    ///
+    /// Set when the frame did not match any filter element and was accepted because of the global filter
+    /// configuration (GFC.ANFS / GFC.ANFE), FIDX is meaningless in this case.
+    #[bits(1)]
+    pub anmf: bool,
+
+    /// Filter Index
+    ///
+    /// 0-127 = index of the matching 11-bit filter element, 0-63 = index of the matching 29-bit filter element
+    #[bits(7)]
+    pub fidx: u8,
+
+    #[bits(2)]
+    _reserved: u8,
+
+    #[bits(1)]
+    pub fdf: FrameFormat,
+
+    /// Bit Rate Switch, only meaningful for FD frames
+    #[bits(1)]
+    pub brs: BitRateSwitch,
+
+    /// Data Length Code
+    ///
+    /// 0-8= CAN + CAN FD: received frame has 0-8 data bytes
+    ///
+    /// 9-15= CAN: received frame has 8 data bytes
+    ///
+    /// 9-15=CAN FD: received frame has 12/16/20/24/32/48/64 data bytes
+    #[bits(4)]
+    pub dlc: u8,
+
+    /// Rx Timestamp, timestamp counter value captured on start of frame reception
+    #[bits(16)]
+    pub rxts: u16,
+}
+
+/// First word of a Tx Event FIFO element, copied from T0 of the transmitted Tx Buffer element.
+#[bitfield(u32, order = Msb, debug = false, defmt = cfg(feature = "defmt"))]
+pub struct TxEventFifoElementE0 {
+    /// Error State Indicator
+    #[bits(1)]
+    pub esi: Esi,
+
+    /// Extended Identifier
+    #[bits(1)]
+    pub xtd: Xtd,
+
+    /// Remote Transmission Request
+    #[bits(1)]
+    pub rtr: Rtr,
+
+    /// Standard or extended identifier depending on bit XTD. A standard identifier is stored into ID 28:18.
+    #[bits(29)]
+    pub id: u32,
+}
+
+/// Second word of a Tx Event FIFO element.
+#[bitfield(u32, order = Msb, debug = false, defmt = cfg(feature = "defmt"))]
+pub struct TxEventFifoElementE1 {
+    /// Message Marker, copied from the Tx Buffer element
+    #[bits(8)]
+    pub mm: u8,
+
+    /// Event Type
+    #[bits(2)]
+    pub et: EventType,
+
+    #[bits(1)]
+    pub fdf: FrameFormat,
+
+    #[bits(1)]
+    pub brs: BitRateSwitch,
+
+    /// Data Length Code, copied from the Tx Buffer element
+    #[bits(4)]
+    pub dlc: u8,
+
+    /// Tx Timestamp, timestamp counter value captured on start of frame transmission
+    #[bits(16)]
+    pub txts: u16,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum EventType {
+    /// 00 and 11 = Reserved
+    Reserved = 0b00,
+    /// 01 = Tx event
+    TxEvent = 0b01,
+    /// 10 = Transmission in spite of cancellation (always set for transmissions in DAR mode)
+    TransmissionInSpiteOfCancellation = 0b10,
+}
+
+impl EventType {
+    const fn into_bits(self) -> u8 {
+        self as u8
+    }
+
+    const fn from_bits(value: u8) -> EventType {
+        match value {
+            0b01 => EventType::TxEvent,
+            0b10 => EventType::TransmissionInSpiteOfCancellation,
+            _ => EventType::Reserved,
+        }
+    }
+}
+
+/// First word of a trigger memory element (TTCAN, FDCAN1 only).
+///
+/// The trigger memory holds up to 64 trigger elements, ordered by time mark and then by cycle code, that drive
+/// the time-triggered schedule. It is located by TTTMC.TMSA and TTTMC.TME.
+#[bitfield(u32, order = Msb, debug = false, defmt = cfg(feature = "defmt"))]
+pub struct TriggerMemoryElementT0 {
+    /// Time Mark, cycle time at which the trigger becomes active
+    #[bits(16)]
+    pub tm: u16,
+
+    #[bits(1)]
+    _reserved1: u8,
+
+    /// Cycle Code, the basic cycles of the matrix cycle in which the trigger is valid
+    #[bits(7)]
+    pub cc: u8,
+
+    #[bits(2)]
+    _reserved2: u8,
+
+    /// Asynchronous Serial Communication, only evaluated by Tx triggers
+    #[bits(2)]
+    pub asc: u8,
+
+    #[bits(4)]
+    pub trigger_type: TriggerType,
+}
+
+/// Second word of a trigger memory element.
+#[bitfield(u32, order = Msb, debug = false, defmt = cfg(feature = "defmt"))]
+pub struct TriggerMemoryElementT1 {
+    #[bits(8)]
+    _reserved1: u8,
+
+    /// Filter Type, whether `mnr` refers to an 11-bit or a 29-bit filter element for Rx triggers
+    #[bits(1)]
+    pub ftype: Xtd,
+
+    /// Message Number, Tx buffer index for Tx triggers, filter element index for Rx triggers
+    #[bits(7)]
+    pub mnr: u8,
+
+    #[bits(10)]
+    _reserved2: u16,
+
+    /// Time Mark Event External, generates a pulse on the event trigger output
+    #[bits(1)]
+    pub tmex: bool,
+
+    /// Time Mark Event Internal, sets TTIR.TTMI
+    #[bits(1)]
+    pub tmin: bool,
+
+    #[bits(4)]
+    _reserved3: u8,
+}
+
+/// Trigger element type (TYPE)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum TriggerType {
+    /// 0000 = Reference message transmission, time master only
+    TxRefTrigger = 0b0000,
+    /// 0001 = Reference message transmission at the end of a gap, time master only
+    TxRefTriggerGap = 0b0001,
+    /// 0010 = Exclusive window, message transmitted once
+    TxTriggerSingle = 0b0010,
+    /// 0011 = Exclusive window, message transmitted in every cycle matching the cycle code
+    TxTriggerContinuous = 0b0011,
+    /// 0100 = Start of an arbitrating window
+    TxTriggerArbitration = 0b0100,
+    /// 0101 = Start of a merged arbitrating window
+    TxTriggerMerged = 0b0101,
+    /// 0110 = Checks for the missing reference message
+    WatchTrigger = 0b0110,
+    /// 0111 = Watch trigger within a gap
+    WatchTriggerGap = 0b0111,
+    /// 1000 = Checks for the reception of a periodic message
+    RxTrigger = 0b1000,
+    /// 1001 = Time base trigger, only generates time mark events
+    TimeBaseTrigger = 0b1001,
+    /// 1010..1111 = End of the trigger list
+    EndOfList = 0b1010,
+}
+
+impl TriggerType {
+    const fn into_bits(self) -> u8 {
+        self as u8
+    }
+
+    const fn from_bits(value: u8) -> TriggerType {
+        match value {
+            0b0000 => TriggerType::TxRefTrigger,
+            0b0001 => TriggerType::TxRefTriggerGap,
+            0b0010 => TriggerType::TxTriggerSingle,
+            0b0011 => TriggerType::TxTriggerContinuous,
+            0b0100 => TriggerType::TxTriggerArbitration,
+            0b0101 => TriggerType::TxTriggerMerged,
+            0b0110 => TriggerType::WatchTrigger,
+            0b0111 => TriggerType::WatchTriggerGap,
+            0b1000 => TriggerType::RxTrigger,
+            0b1001 => TriggerType::TimeBaseTrigger,
+            _ => TriggerType::EndOfList,
+        }
+    }
+}
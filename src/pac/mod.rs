@@ -1,3 +1,5 @@
+#[cfg(feature = "h7")]
+pub mod ccu;
 pub mod message_ram;
 pub mod registers;
 
@@ -15,6 +17,27 @@ pub(crate) mod mapping {
     pub(crate) const FDCAN2_REGISTER_BLOCK_ADDR: *mut () = 0x4000_6800 as *mut ();
     pub(crate) const FDCAN_MSGRAM_ADDR: *mut u32 = 0x4000_B400 as *mut u32;
     pub(crate) const FDCAN_MSGRAM_LEN_WORDS: usize = 512;
+
+    /// Per instance element limits, layout is fixed in hardware (212 words per instance).
+    /// Only the regions the driver accesses are listed, the 28 standard and 8 extended filters are not used yet.
+    pub(crate) mod limits {
+        pub(crate) const RX_FIFO: u8 = 3;
+        pub(crate) const TX_EVENT_FIFO: u8 = 3;
+        pub(crate) const TX_BUFFERS: u8 = 3;
+    }
+
+    /// Word offsets of the fixed message RAM layout, FDCAN1 section comes first, FDCAN2 right after it.
+    pub(crate) mod fixed_layout {
+        pub(crate) const INSTANCE_WORDS: u16 = 212;
+        pub(crate) const RX_FIFO0_ADDR: u16 = 44;
+        pub(crate) const RX_FIFO1_ADDR: u16 = 98;
+        /// R0, R1 and 64 data bytes
+        pub(crate) const RX_ELEMENT_WORDS: usize = 18;
+        pub(crate) const TX_EVENT_FIFO_ADDR: u16 = 152;
+        pub(crate) const TX_BUFFERS_ADDR: u16 = 158;
+        /// T0, T1 and 64 data bytes
+        pub(crate) const TX_ELEMENT_WORDS: usize = 18;
+    }
 }
 
 #[cfg(feature = "h7")]
@@ -23,9 +46,20 @@ pub(crate) mod mapping {
     pub(crate) const FDCAN1_REGISTER_BLOCK_ADDR: *mut () = 0x4000_A000 as *mut ();
     pub(crate) const FDCAN2_REGISTER_BLOCK_ADDR: *mut () = 0x4000_A400 as *mut ();
     pub(crate) const FDCAN3_REGISTER_BLOCK_ADDR: *mut () = 0x4000_D400 as *mut ();
-    // pub(crate) const FDCAN_CCU_REGISTER_BLOCK_ADDR: *mut () = 0x4000_A800 as *mut ();
+    pub(crate) const FDCAN_CCU_REGISTER_BLOCK_ADDR: *mut () = 0x4000_A800 as *mut ();
     pub(crate) const FDCAN_MSGRAM_ADDR: *mut u32 = 0x4000_AC00 as *mut u32;
     pub(crate) const FDCAN_MSGRAM_LEN_WORDS: usize = 2560;
+
+    /// Per instance element limits imposed by the size of the fields in the configuration registers.
+    pub(crate) mod limits {
+        pub(crate) const ELEVEN_BIT_FILTERS: u8 = 128;
+        pub(crate) const TWENTY_NINE_BIT_FILTERS: u8 = 64;
+        pub(crate) const RX_FIFO: u8 = 64;
+        pub(crate) const RX_BUFFERS: u8 = 64;
+        pub(crate) const TX_EVENT_FIFO: u8 = 32;
+        pub(crate) const TX_BUFFERS: u8 = 32;
+        pub(crate) const TRIGGER_MEMORY: u8 = 64;
+    }
 }
 
 pub(crate) use mapping::*;
@@ -457,6 +457,17 @@ pub mod regs {
         pub fn set_bse(&mut self, val: bool) {
             self.0 = (self.0 & !(0x01 << 9usize)) | (((val as u32) & 0x01) << 9usize);
         }
+        #[doc = "Wide Message Marker"]
+        #[inline(always)]
+        pub const fn wmm(&self) -> bool {
+            let val = (self.0 >> 11usize) & 0x01;
+            val != 0
+        }
+        #[doc = "Wide Message Marker"]
+        #[inline(always)]
+        pub fn set_wmm(&mut self, val: bool) {
+            self.0 = (self.0 & !(0x01 << 11usize)) | (((val as u32) & 0x01) << 11usize);
+        }
         #[doc = "Protocol Exception Handling Disable"]
         #[inline(always)]
         pub const fn pxhd(&self) -> bool {
@@ -0,0 +1,196 @@
+//! Time-triggered CAN (TTCAN, ISO 11898-4) trigger memory and operation mode (H7 FDCAN1 only).
+//!
+//! Trigger memory is allocated by the [MessageRamBuilder](crate::MessageRamBuilder) as the last step of a
+//! layout and filled with [configure_trigger](FdCan::configure_trigger). Only FDCAN1 has the TT registers,
+//! on the other instances the trigger memory allocation is ignored by the hardware.
+//!
+//! This covers a minimal schedule only: trigger elements, the operation mode and the expected number of Tx
+//! triggers. Cycle time, reference message and global time settings keep their reset values.
+
+use crate::fdcan::{ConfigMode, Error, FdCan, FdCanInstance};
+use crate::pac::message_ram::{TriggerMemoryElementT0, TriggerMemoryElementT1, Xtd};
+
+pub use crate::pac::message_ram::TriggerType;
+
+/// TTCAN operation mode (TTOCF.OM)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TtOperationMode {
+    /// Event-driven CAN communication, TTCAN disabled (reset value)
+    EventDriven = 0b00,
+    /// TTCAN level 1
+    Level1 = 0b01,
+    /// TTCAN level 2
+    Level2 = 0b10,
+    /// TTCAN level 0, event-driven communication with an external event synchronized time base
+    Level0 = 0b11,
+}
+
+/// One trigger memory element.
+///
+/// Triggers are evaluated in the order of the trigger memory, elements have to be sorted by time mark and, for
+/// equal time marks, by cycle code. The list ends with the first [TriggerType::EndOfList] element or at the end
+/// of the allocated trigger memory.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Trigger {
+    pub trigger_type: TriggerType,
+    /// Cycle time at which the trigger becomes active
+    pub time_mark: u16,
+    /// Basic cycles of the matrix cycle in which the trigger is valid (7 bits)
+    pub cycle_code: u8,
+    /// Tx buffer index for Tx triggers, filter element index for Rx triggers (7 bits)
+    pub message_number: u8,
+    /// `message_number` refers to a 29-bit filter element instead of an 11-bit one, Rx triggers only
+    pub extended_filter: bool,
+    /// Set TTIR.TTMI when the time mark is reached
+    pub internal_event: bool,
+    /// Pulse the event trigger output when the time mark is reached
+    pub external_event: bool,
+}
+
+impl Trigger {
+    /// Trigger without time mark events, for an 11-bit filter element if it is an Rx trigger.
+    pub const fn new(
+        trigger_type: TriggerType,
+        time_mark: u16,
+        cycle_code: u8,
+        message_number: u8,
+    ) -> Self {
+        Trigger {
+            trigger_type,
+            time_mark,
+            cycle_code,
+            message_number,
+            extended_filter: false,
+            internal_event: false,
+            external_event: false,
+        }
+    }
+
+    const fn to_element(self) -> (TriggerMemoryElementT0, TriggerMemoryElementT1) {
+        let t0 = TriggerMemoryElementT0::new()
+            .with_tm(self.time_mark)
+            .with_cc(self.cycle_code & 0x7F)
+            .with_trigger_type(self.trigger_type);
+        let t1 = TriggerMemoryElementT1::new()
+            .with_ftype(if self.extended_filter {
+                Xtd::TwentyNineBits
+            } else {
+                Xtd::ElevenBits
+            })
+            .with_mnr(self.message_number & 0x7F)
+            .with_tmin(self.internal_event)
+            .with_tmex(self.external_event);
+        (t0, t1)
+    }
+
+    const fn from_element(t0: TriggerMemoryElementT0, t1: TriggerMemoryElementT1) -> Self {
+        Trigger {
+            trigger_type: t0.trigger_type(),
+            time_mark: t0.tm(),
+            cycle_code: t0.cc(),
+            message_number: t1.mnr(),
+            extended_filter: matches!(t1.ftype(), Xtd::TwentyNineBits),
+            internal_event: t1.tmin(),
+            external_event: t1.tmex(),
+        }
+    }
+}
+
+impl FdCan<ConfigMode> {
+    /// Writes trigger element `idx` of the trigger memory allocated in the applied layout.
+    ///
+    /// Returns [Error::LayoutNotApplied] before [set_layout](FdCan::set_layout), and
+    /// [Error::TriggerIndexOutOfRange] if `idx` is outside of the allocated trigger memory.
+    pub fn configure_trigger(&mut self, idx: u8, trigger: Trigger) -> Result<(), Error> {
+        if self.config.layout.is_empty() {
+            return Err(Error::LayoutNotApplied);
+        }
+        let element = self.message_ram().trigger_element(idx)?;
+        let (t0, t1) = trigger.to_element();
+        element.t0.write_value(t0);
+        element.t1.write_value(t1);
+        Ok(())
+    }
+
+    /// Selects the TTCAN operation mode, whether this node is a potential time master, and the number of Tx
+    /// triggers in one matrix cycle (TTMLM.ENTT) that the schedule is checked against.
+    ///
+    /// Returns [Error::WrongInstance] unless called on FDCAN1.
+    pub fn set_time_triggered_operation(
+        &mut self,
+        mode: TtOperationMode,
+        time_master: bool,
+        expected_tx_triggers: u16,
+    ) -> Result<(), Error> {
+        if self.instance != FdCanInstance::FdCan1 {
+            return Err(Error::WrongInstance);
+        }
+        self.can.ttocf().modify(|w| {
+            w.set_om(mode as u8);
+            w.set_tm(time_master);
+        });
+        self.can
+            .ttmlm()
+            .modify(|w| w.set_entt(expected_tx_triggers));
+        Ok(())
+    }
+}
+
+impl<M> FdCan<M> {
+    /// Reads back trigger element `idx`, see [configure_trigger](FdCan::configure_trigger).
+    pub fn read_trigger(&self, idx: u8) -> Result<Trigger, Error> {
+        let element = self.message_ram().trigger_element(idx)?;
+        Ok(Trigger::from_element(element.t0.read(), element.t1.read()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fdcan::tests::host_fdcan;
+    use crate::message_ram_builder::MessageRamBuilder;
+    use crate::message_ram_layout::tests::{fifo_layout, host_ram};
+
+    fn trigger() -> Trigger {
+        Trigger {
+            trigger_type: TriggerType::RxTrigger,
+            time_mark: 0xABCD,
+            cycle_code: 0x45,
+            message_number: 0x12,
+            extended_filter: true,
+            internal_event: true,
+            external_event: true,
+        }
+    }
+
+    // Time mark in T0[31:16], cycle code in T0[14:8], type in T0[3:0]; filter type in T1[23], message number in
+    // T1[22:16] and the time mark event bits in T1[5:4], a shifted field would silently break the schedule.
+    #[test]
+    fn element_bit_positions() {
+        let (t0, t1) = trigger().to_element();
+        assert_eq!(t0.into_bits(), 0xABCD_4508);
+        assert_eq!(t1.into_bits(), 0x0092_0030);
+        assert_eq!(Trigger::from_element(t0, t1), trigger());
+    }
+
+    #[test]
+    fn configure_trigger_requires_layout() {
+        let ram = host_ram();
+        let mut can = host_fdcan::<ConfigMode>(FdCanInstance::FdCan1, ram);
+        assert!(matches!(
+            can.configure_trigger(0, trigger()),
+            Err(Error::LayoutNotApplied)
+        ));
+        let builder = MessageRamBuilder::for_region(0, 0x400, FdCanInstance::FdCan1);
+        let (layout, _) = fifo_layout(builder, 0, 1, 1, 2);
+        can.set_layout(layout);
+        can.configure_trigger(1, trigger()).unwrap();
+        assert_eq!(can.read_trigger(1).unwrap(), trigger());
+        assert!(matches!(
+            can.configure_trigger(2, trigger()),
+            Err(Error::TriggerIndexOutOfRange)
+        ));
+    }
+}
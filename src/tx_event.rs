@@ -0,0 +1,272 @@
+//! Tx Event FIFO reader and correlation of completed transmissions with application requests.
+
+use crate::Id;
+use crate::fdcan::{Error, FdCan, Transmit};
+use crate::id::IdReg;
+use crate::message_ram_layout::TxBufferIdx;
+use crate::pac::message_ram::{
+    BitRateSwitch, FrameFormat, TxEventFifoElementE0, TxEventFifoElementE1,
+};
+use crate::tx_rx::{TxFrameHeader, frame_data_len};
+
+/// Event type (ET field) of a Tx Event FIFO element
+pub use crate::pac::message_ram::EventType as TxEventType;
+
+/// Tx Event FIFO element, stored for frames transmitted with a message marker.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TxEvent {
+    pub id: Id,
+    /// Marker copied from [TxFrameHeader::marker](crate::TxFrameHeader::marker), upper byte is 0 unless
+    /// wide message markers (H7 only) are enabled
+    pub marker: u16,
+    pub event_type: TxEventType,
+    pub frame_format: FrameFormat,
+    pub bit_rate_switching: bool,
+    /// Number of data bytes
+    pub len: u8,
+    /// Timestamp counter value captured on start of frame transmission, only the lower byte is available with
+    /// wide message markers
+    pub timestamp: u16,
+}
+
+impl TxEvent {
+    /// Decode E0 and E1 words of a Tx Event FIFO element, with CCCR.WMM set E1 bits 15:8 hold the upper marker byte.
+    fn decode(e0: TxEventFifoElementE0, e1: TxEventFifoElementE1, wide_marker: bool) -> Self {
+        let (marker, timestamp) = if wide_marker {
+            (
+                u16::from(e1.mm()) | (e1.txts() & 0xFF00),
+                e1.txts() & 0x00FF,
+            )
+        } else {
+            (u16::from(e1.mm()), e1.txts())
+        };
+        let frame_format = e1.fdf();
+        let fd = matches!(frame_format, FrameFormat::FD);
+        Self {
+            id: IdReg::from_element_word(e0.into_bits()).to_id(),
+            marker,
+            event_type: e1.et(),
+            frame_format,
+            bit_rate_switching: fd && matches!(e1.brs(), BitRateSwitch::Switch),
+            len: frame_data_len(e1.dlc(), fd) as u8,
+            timestamp,
+        }
+    }
+}
+
+impl<M: Transmit> FdCan<M> {
+    /// Reads and acknowledges the oldest Tx event, returns [Error::WouldBlock] if the Tx Event FIFO is empty.
+    pub fn read_tx_event(&mut self) -> Result<TxEvent, Error> {
+        let status = self.can.txefs().read();
+        if status.effl() == 0 {
+            return Err(Error::WouldBlock);
+        }
+        let idx = status.efgi();
+        let element = self.message_ram().tx_event_element(idx)?;
+        #[cfg(feature = "h7")]
+        let wide_marker = self.config.wide_message_marker;
+        #[cfg(not(feature = "h7"))]
+        let wide_marker = false;
+        let event = TxEvent::decode(element.e0.read(), element.e1.read(), wide_marker);
+        self.can.txefa().write(|w| w.set_efai(idx));
+        if matches!(event.frame_format, FrameFormat::FD) {
+            self.fd_tx_completed = true;
+        }
+        Ok(event)
+    }
+
+    /// Returns `true` once a Tx event for an FD frame was read with [read_tx_event](Self::read_tx_event),
+    /// i.e. at least one FD frame was acknowledged on the bus.
+    ///
+    /// Only frames sent with Tx events enabled are visible here, TX completion flags do not carry the frame
+    /// format. Sticky like [fd_seen](Self::fd_seen).
+    #[inline]
+    pub fn fd_tx_completed(&self) -> bool {
+        self.fd_tx_completed
+    }
+}
+
+/// Completion of a tracked transmit request, see [TxTracker].
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TxCompletion<T> {
+    /// Application level request ID passed to [TxTracker::track]
+    pub request: T,
+    pub event: TxEvent,
+}
+
+/// Maps message markers to application level request IDs, so that Tx events can be turned into
+/// "which of my requests completed".
+///
+/// Up to `N` requests can be outstanding, the slot index is used as the marker. `N` above 256 requires
+/// wide message markers (`FdCanConfig::wide_message_marker`, H7 only):
+/// ```ignore
+/// let mut tracker = TxTracker::<u32, 8>::new();
+/// let marker = tracker.track(request_id).ok_or(MyError::TooManyInFlight)?;
+/// header.marker = Some(marker);
+/// can.transmit_fifo(header, data)?;
+/// // later, e.g. after TX completion interrupt
+/// while let Some(completion) = tracker.poll(&mut can) { ... }
+/// ```
+pub struct TxTracker<T, const N: usize> {
+    slots: [Option<T>; N],
+}
+
+impl<T: Copy, const N: usize> TxTracker<T, N> {
+    pub const fn new() -> Self {
+        assert!(N <= 65536, "message marker is at most 16 bits wide");
+        Self { slots: [None; N] }
+    }
+
+    /// Remembers `request` and returns a marker to put into [TxFrameHeader::marker](crate::TxFrameHeader::marker),
+    /// or `None` if all `N` slots are in use.
+    pub fn track(&mut self, request: T) -> Option<u16> {
+        let (marker, slot) = self
+            .slots
+            .iter_mut()
+            .enumerate()
+            .find(|(_, slot)| slot.is_none())?;
+        *slot = Some(request);
+        Some(marker as u16)
+    }
+
+    /// Forgets a tracked request, e.g. if the frame was aborted and no Tx event will ever be stored for it.
+    pub fn forget(&mut self, marker: u16) -> Option<T> {
+        self.slots.get_mut(marker as usize)?.take()
+    }
+
+    /// Matches a Tx event to a tracked request and frees its slot. Events with unknown markers return `None`.
+    pub fn complete(&mut self, event: TxEvent) -> Option<TxCompletion<T>> {
+        let request = self.forget(event.marker)?;
+        Some(TxCompletion { request, event })
+    }
+
+    /// Reads Tx events until one of the tracked requests completes or the Tx Event FIFO is empty.
+    pub fn poll<M: Transmit>(&mut self, can: &mut FdCan<M>) -> Option<TxCompletion<T>> {
+        while let Ok(event) = can.read_tx_event() {
+            if let Some(completion) = self.complete(event) {
+                return Some(completion);
+            }
+        }
+        None
+    }
+
+    /// Number of requests waiting for their Tx event.
+    pub fn in_flight(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_some()).count()
+    }
+}
+
+impl<T: Copy, const N: usize> Default for TxTracker<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Outcome of matching a Tx event against the order frames were put into the TX FIFO, see [TxOrderTracker].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TxOrder {
+    /// Frame with this sequence number left right after the previous one
+    InOrder(u16),
+    /// Frame `got` left while `expected` was still due, frames in between were skipped (aborted) or reordered
+    OutOfOrder { expected: u16, got: u16 },
+}
+
+/// Confirms that a sequence of frames left the node in the order it was enqueued.
+///
+/// Each frame put through [transmit](Self::transmit) gets the next sequence number as its message marker, Tx
+/// events then have to come back with consecutive markers. In FIFO mode (TXBC.TFQM = 0, the default) the core
+/// sends frames in put order, so [TxOrder::OutOfOrder] only shows up if a frame was aborted or lost. In Queue mode
+/// frames go out by ID priority and are expected to reorder.
+///
+/// The tracker assumes it owns the Tx Event FIFO: events of frames sent around it are reported as out of order.
+/// ```ignore
+/// let mut order = TxOrderTracker::new(false);
+/// for chunk in payload.chunks(64) {
+///     order.transmit(&mut can, header, chunk)?;
+/// }
+/// // later, e.g. after TX event interrupt
+/// while let Some(result) = order.poll(&mut can) {
+///     if let TxOrder::OutOfOrder { .. } = result { restart_transfer() }
+/// }
+/// ```
+pub struct TxOrderTracker {
+    next_put: u16,
+    next_expected: u16,
+    mask: u16,
+}
+
+impl TxOrderTracker {
+    /// Sequence numbers wrap at 256, or at 65536 with `wide_markers` if wide message markers are enabled.
+    pub const fn new(wide_markers: bool) -> Self {
+        Self {
+            next_put: 0,
+            next_expected: 0,
+            mask: if wide_markers { 0xFFFF } else { 0x00FF },
+        }
+    }
+
+    /// Puts a frame into the TX FIFO with the next sequence number as marker, see
+    /// [transmit_fifo](FdCan::transmit_fifo). Returns the buffer used and the sequence number.
+    pub fn transmit<M: Transmit>(
+        &mut self,
+        can: &mut FdCan<M>,
+        mut tx_header: TxFrameHeader,
+        data: &[u8],
+    ) -> Result<(TxBufferIdx, u16), Error> {
+        let seq = self.next_put;
+        tx_header.marker = Some(seq);
+        let idx = can.transmit_fifo(tx_header, data)?;
+        self.next_put = seq.wrapping_add(1) & self.mask;
+        Ok((idx, seq))
+    }
+
+    /// Matches a Tx event to the expected sequence number. After a mismatch, the sequence continues from the
+    /// received marker so a single gap is reported only once.
+    pub fn complete(&mut self, event: &TxEvent) -> TxOrder {
+        let got = event.marker & self.mask;
+        let expected = self.next_expected;
+        self.next_expected = got.wrapping_add(1) & self.mask;
+        if got == expected {
+            TxOrder::InOrder(got)
+        } else {
+            TxOrder::OutOfOrder { expected, got }
+        }
+    }
+
+    /// Reads one Tx event and matches it, returns `None` if the Tx Event FIFO is empty.
+    pub fn poll<M: Transmit>(&mut self, can: &mut FdCan<M>) -> Option<TxOrder> {
+        let event = can.read_tx_event().ok()?;
+        Some(self.complete(&event))
+    }
+
+    /// Number of frames put since the last one confirmed.
+    pub fn in_flight(&self) -> u16 {
+        self.next_put.wrapping_sub(self.next_expected) & self.mask
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_keeps_the_event_type() {
+        let e0 = TxEventFifoElementE0::new();
+        for et in [
+            TxEventType::TxEvent,
+            TxEventType::TransmissionInSpiteOfCancellation,
+        ] {
+            let e1 = TxEventFifoElementE1::new()
+                .with_mm(7)
+                .with_et(et)
+                .with_dlc(8);
+            let event = TxEvent::decode(e0, e1, false);
+            assert_eq!(event.event_type, et);
+            assert_eq!(event.marker, 7);
+            assert_eq!(event.len, 8);
+        }
+    }
+}
@@ -1,7 +1,17 @@
 use crate::Id;
-use crate::fdcan::Transmit;
-use crate::message_ram_layout::TxBufferIdx;
-use crate::pac::message_ram::{Esi, FrameFormat};
+use crate::fdcan::{Receive, Transmit};
+use crate::id::IdReg;
+#[cfg(feature = "h7")]
+use crate::message_ram_layout::DataFieldSize;
+use crate::message_ram_layout::{FIFONr, RxBufferElement, TxBufferIdx};
+use crate::pac::message_ram::{
+    BitRateSwitch, Esi, FrameFormat, Rtr, RxBufferElementR0, RxBufferElementR1,
+};
+#[cfg(feature = "h7")]
+use crate::pac::registers::regs::Txbar;
+use crate::pac::registers::regs::Txbcr;
+#[cfg(feature = "h7")]
+use crate::pac::registers::regs::{Ndat1, Ndat2};
 use crate::util::checked_wait;
 use crate::{Error, FdCan};
 
@@ -27,6 +37,7 @@ pub enum Dlc {
 }
 
 impl Dlc {
+    #[cfg(feature = "h7")]
     const fn len(&self) -> u8 {
         *self as u8
     }
@@ -53,7 +64,13 @@ impl Dlc {
         }
     }
 
-    pub(crate) fn reg_value(&self) -> u8 {
+    /// Returns `true` if a frame with this data length can be stored in an element with the given data field size.
+    #[cfg(feature = "h7")]
+    pub const fn fits_in(&self, size: DataFieldSize) -> bool {
+        self.len() <= size.max_len()
+    }
+
+    pub(crate) const fn reg_value(&self) -> u8 {
         match self {
             Dlc::_0Bytes => 0,
             Dlc::_1Bytes => 1,
@@ -90,21 +107,191 @@ pub struct TxFrameHeader {
     pub bit_rate_switching: bool,
     /// Whether this node is error passive or not
     pub error_state: Esi,
-    pub marker: Option<u8>,
+    /// Message marker, echoed in the Tx Event FIFO. When set, a Tx event is stored for this frame.
+    ///
+    /// The upper byte is only used when wide message markers (CCCR.WMM, H7 only) are enabled, otherwise it is
+    /// ignored.
+    pub marker: Option<u16>,
+    /// Capture a timestamp when the frame is transmitted (TSCE).
+    ///
+    /// Only used by M_CAN cores with an external timestamping unit, the H7 and G0 FDCAN ignore it.
+    pub timestamp_capture: bool,
 }
 
 impl TxFrameHeader {
-    pub fn fd_brs(id: Id) -> Self {
+    /// Classic CAN frame without a Tx event, use the chainable methods to change that:
+    /// `TxFrameHeader::new(id).fd().brs().marker(7)`.
+    pub const fn new(id: Id) -> Self {
         Self {
-            frame_format: FrameFormat::FD,
+            frame_format: FrameFormat::Classic,
             id,
-            bit_rate_switching: true,
+            bit_rate_switching: false,
             error_state: Esi::EsiDependsOnErrorPassive,
             marker: None,
+            timestamp_capture: false,
+        }
+    }
+
+    pub const fn fd_brs(id: Id) -> Self {
+        Self::new(id).fd().brs()
+    }
+
+    /// Send as an FD frame, ignored if the global frame transmission config is classic only.
+    pub const fn fd(mut self) -> Self {
+        self.frame_format = FrameFormat::FD;
+        self
+    }
+
+    /// Switch to the data bit rate, only has an effect for [FD](Self::fd) frames.
+    pub const fn brs(mut self) -> Self {
+        self.bit_rate_switching = true;
+        self
+    }
+
+    /// Transmit the ESI bit recessive regardless of the error state of this node.
+    pub const fn esi_recessive(mut self) -> Self {
+        self.error_state = Esi::EsiTransmittedRecessive;
+        self
+    }
+
+    /// Sets the message marker and stores a Tx event for this frame.
+    pub const fn marker(mut self, marker: u16) -> Self {
+        self.marker = Some(marker);
+        self
+    }
+
+    /// Stores a Tx event for this frame, with marker 0 unless one was already set with [marker](Self::marker).
+    pub const fn store_tx_event(mut self) -> Self {
+        if self.marker.is_none() {
+            self.marker = Some(0);
+        }
+        self
+    }
+
+    /// Requests a transmit timestamp, see [timestamp_capture](Self::timestamp_capture).
+    pub const fn capture_timestamp(mut self) -> Self {
+        self.timestamp_capture = true;
+        self
+    }
+}
+
+/// Header of a received frame
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RxFrameInfo {
+    /// Type of message - Classical or FD.
+    pub frame_format: FrameFormat,
+    /// Id
+    pub id: Id,
+    /// Remote frame was received (RTR bit set)
+    pub remote: bool,
+    /// Whether data phase was transmitted with bit rate switching
+    pub bit_rate_switching: bool,
+    /// Error state of the transmitting node (ESI bit)
+    pub error_state: Esi,
+    /// Number of data bytes
+    pub len: u8,
+    /// The frame carried more data than fits into the RX element (data field size configured smaller than the
+    /// frame's DLC), `len` is the number of bytes that were stored and the rest is lost.
+    pub truncated: bool,
+    /// Timestamp counter value captured on start of frame reception
+    pub timestamp: u16,
+    /// Index of the filter element that accepted the frame (FIDX), `None` if no filter matched and the frame
+    /// was accepted by the global filter configuration (ANMF bit set)
+    pub matched_filter: Option<u8>,
+}
+
+impl RxFrameInfo {
+    /// Decode R0 and R1 words of an RX buffer or FIFO element.
+    pub(crate) fn decode(r0: RxBufferElementR0, r1: RxBufferElementR1) -> Self {
+        let frame_format = r1.fdf();
+        let fd = matches!(frame_format, FrameFormat::FD);
+        let id = IdReg::from_element_word(r0.into_bits());
+        Self {
+            frame_format,
+            id: id.to_id(),
+            remote: matches!(r0.rtr(), Rtr::TransmitRemoteFrame),
+            // BRS and ESI only exist in FD frames, don't trust the bits otherwise, a classic frame always reads
+            // as error active.
+            bit_rate_switching: fd && matches!(r1.brs(), BitRateSwitch::Switch),
+            error_state: if fd {
+                r0.esi()
+            } else {
+                Esi::EsiDependsOnErrorPassive
+            },
+            len: frame_data_len(r1.dlc(), fd) as u8,
+            truncated: false,
+            timestamp: r1.rxts(),
+            matched_filter: if r1.anmf() { None } else { Some(r1.fidx()) },
         }
     }
 }
 
+/// Index of the element `offset` places after the get index `first`, wrapping around at the FIFO end.
+const fn fifo_index(first: u8, offset: usize, fifo_len: usize) -> u8 {
+    ((first as usize + offset) % fifo_len) as u8
+}
+
+/// Number of data bytes for a DLC register value, classic frames carry at most 8 bytes.
+///
+/// Single source of truth for the DLC mapping: received elements and Tx events are decoded with it, and a
+/// frame is only written for transmission if its length maps back to itself.
+pub(crate) const fn frame_data_len(dlc_reg: u8, fd: bool) -> usize {
+    match dlc_reg {
+        0..=8 => dlc_reg as usize,
+        _ if !fd => 8,
+        9 => 12,
+        10 => 16,
+        11 => 20,
+        12 => 24,
+        13 => 32,
+        14 => 48,
+        _ => 64,
+    }
+}
+
+/// Received frame with its data copied out of message RAM
+#[derive(Copy, Clone)]
+pub struct RxFrame {
+    /// Decoded frame header
+    pub info: RxFrameInfo,
+    data: [u8; 64],
+}
+
+impl RxFrame {
+    /// Received data, `info.len` bytes long.
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.info.len as usize]
+    }
+}
+
+/// Software receive counters, see [rx_stats](FdCan::rx_stats).
+///
+/// Counted by this driver while reading frames out of message RAM, so frames consumed elsewhere (e.g. directly
+/// through raw register access) are missing. All counters wrap around on overflow.
+#[cfg(feature = "stats")]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RxStats {
+    /// Frames read from RX FIFO0
+    pub fifo0: u32,
+    /// Frames read from RX FIFO1
+    pub fifo1: u32,
+    /// Frames read from dedicated RX buffers
+    pub dedicated: u32,
+    /// Frames that did not match any filter and were accepted by the global filter instead.
+    ///
+    /// Frames rejected by the filters never reach message RAM and are not reported by the core in any way,
+    /// so they cannot be counted. Comparing this counter against the total hints at filters that are too wide.
+    pub non_matching: u32,
+    /// Message lost events serviced on either RX FIFO (IR.RFnL), by the interrupt handler with the `asynchronous`
+    /// feature or by [take_rx_fifo_lost](FdCan::take_rx_fifo_lost) otherwise. The flag is sticky until cleared,
+    /// so any number of frames dropped between two services because the FIFO was full in blocking mode count as
+    /// one. Only counted on H7.
+    pub overrun: u32,
+}
+
 impl<M: Transmit> FdCan<M> {
     // Puts a CAN frame in a transmit mailbox for transmission on the bus.
     //
@@ -173,6 +360,14 @@ impl<M: Transmit> FdCan<M> {
     //     Ok(pending_frame)
     // }
 
+    /// Raw TX FIFO/Queue put index (TXFQS.TFQPI), i.e. the buffer index the next frame will be placed into.
+    ///
+    /// Only meaningful if FIFO/Queue is not full, see [tx_queue_is_full](Self::tx_queue_is_full).
+    #[inline]
+    pub fn tx_fifo_put_index(&self) -> u8 {
+        self.can.txfqs().read().tfqpi()
+    }
+
     /// Returns if the tx queue is able to accept new messages without having to cancel an existing one
     #[inline]
     pub fn tx_queue_is_full(&self) -> bool {
@@ -194,7 +389,41 @@ impl<M: Transmit> FdCan<M> {
     //     }
     // }
 
+    /// Write dedicated TX buffer without requesting transmission, see [transmit_all_pending](Self::transmit_all_pending).
+    #[cfg(feature = "h7")]
+    pub fn write_tx_buffer(
+        &mut self,
+        idx: TxBufferIdx,
+        tx_header: TxFrameHeader,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        if idx.instance != self.instance {
+            return Err(Error::WrongInstance);
+        }
+        let tx_buffer = self.message_ram().tx_buffer(idx)?;
+        self.write_tx_element(tx_buffer, &tx_header, data)?;
+        self.tx_staged |= 1 << idx.idx();
+        Ok(())
+    }
+
+    /// Requests transmission of every buffer staged with [write_tx_buffer](Self::write_tx_buffer) with a single
+    /// TXBAR write, so that all of them enter the next internal arbitration round together.
+    ///
+    /// Among pending dedicated buffers the core transmits the one with the lowest message ID first (lowest buffer
+    /// index only breaks ties between equal IDs), not the one with the lowest index. Staging a set of frames and
+    /// requesting them at once lets them contend by ID as on the bus, requesting them one by one lets an earlier
+    /// requested lower priority frame start first.
+    #[cfg(feature = "h7")]
+    pub fn transmit_all_pending(&mut self) {
+        let staged = core::mem::take(&mut self.tx_staged);
+        if staged != 0 {
+            self.can.txbar().write_value(Txbar(staged));
+        }
+    }
+
     /// Write dedicated TX buffer and set the corresponding "add request" bit.
+    ///
+    /// Returns [Error::LayoutNotApplied] if no layout was applied to this instance yet.
     #[cfg(feature = "h7")]
     pub fn write_tx_buffer_pend(
         &mut self,
@@ -205,31 +434,124 @@ impl<M: Transmit> FdCan<M> {
         if idx.instance != self.instance {
             return Err(Error::WrongInstance);
         }
-        let mut tx_buffer = self.message_ram().tx_buffer(idx)?;
-        let Some(dlc) = Dlc::from_len(data.len()) else {
-            return Err(Error::WrongDataSize);
+        let tx_buffer = self.message_ram().tx_buffer(idx)?;
+        self.write_tx_element(tx_buffer, &tx_header, data)?;
+
+        // Set as ready to transmit
+        _ = self.tx_buffer_pend(idx);
+        Ok(())
+    }
+
+    /// Writes the frame into the first buffer of `buffers` that has no pending transmission request and requests
+    /// it, the dedicated buffer analogue of [transmit_fifo](Self::transmit_fifo).
+    ///
+    /// Buffers staged with [write_tx_buffer](Self::write_tx_buffer) but not requested yet are skipped as well.
+    /// Order `buffers` by preference, returns the buffer that was used or [Error::WouldBlock] if all of them are
+    /// busy.
+    #[cfg(feature = "h7")]
+    pub fn transmit_any(
+        &mut self,
+        buffers: &[TxBufferIdx],
+        tx_header: TxFrameHeader,
+        data: &[u8],
+    ) -> Result<TxBufferIdx, Error> {
+        if buffers.iter().any(|idx| idx.instance != self.instance) {
+            return Err(Error::WrongInstance);
+        }
+        let busy = self.can.txbrp().read().0 | self.tx_staged;
+        let Some(&idx) = buffers.iter().find(|idx| busy & (1 << idx.idx()) == 0) else {
+            return Err(Error::WouldBlock);
         };
-        if dlc.len() > self.config.layout.tx_buffers_data_size.max_len() {
-            return Err(Error::WrongDataSize);
+        self.write_tx_buffer_pend(idx, tx_header, data)?;
+        Ok(idx)
+    }
+
+    /// Write dedicated TX buffer, request transmission and wait until the frame actually left.
+    ///
+    /// Returns [Error::Timeout] if the frame is still pending after `timeout_iterations`, it stays pending and can
+    /// be cancelled with [abort_blocking](Self::abort_blocking). Returns [Error::TransmissionNotCompleted] if the
+    /// request finished without a successful transmission, e.g. it was cancelled or, with automatic retransmission
+    /// disabled, arbitration was lost or an error occurred.
+    #[cfg(feature = "h7")]
+    pub fn transmit_buffer_blocking(
+        &mut self,
+        idx: TxBufferIdx,
+        tx_header: TxFrameHeader,
+        data: &[u8],
+        timeout_iterations: u32,
+    ) -> Result<(), Error> {
+        self.write_tx_buffer_pend(idx, tx_header, data)?;
+        checked_wait(|| self.has_pending_frame(idx), timeout_iterations)?;
+        self.clear_transmission_completed_flag();
+        if self.is_transmission_complete(idx)? {
+            Ok(())
+        } else {
+            Err(Error::TransmissionNotCompleted)
         }
+    }
 
-        tx_buffer.fill(&tx_header, dlc);
+    /// Returns `true` if the last transmission request of the buffer completed successfully (TXBTO).
+    /// The flag is reset when a new transmission is requested for the same buffer.
+    #[inline]
+    pub fn is_transmission_complete(&self, idx: TxBufferIdx) -> Result<bool, Error> {
+        if idx.instance != self.instance {
+            return Err(Error::WrongInstance);
+        }
+        Ok(self.can.txbto().read().to(idx.idx()))
+    }
 
-        let mut chunks = data.chunks(4);
-        for d in tx_buffer.data {
-            let Some(chunk) = chunks.next() else {
-                break;
-            };
-            let word = if chunk.len() == 4 {
-                let word: [u8; 4] = chunk.try_into().expect("length is 4");
-                u32::from_le_bytes(word)
-            } else {
-                let mut word = [0u8; 4];
-                word[..chunk.len()].copy_from_slice(chunk);
-                u32::from_le_bytes(word)
-            };
-            *d = word;
+    /// Enables or disables the transmission completed interrupt of one TX buffer (TXBTIE), e.g. to only wake on
+    /// completion of a latency-sensitive buffer while bulk buffers complete silently.
+    ///
+    /// Only has an effect while the TC interrupt is enabled. With the `asynchronous` feature, all buffers are
+    /// enabled again every time the instance leaves config mode.
+    #[inline]
+    pub fn set_tx_buffer_interrupt(
+        &mut self,
+        idx: TxBufferIdx,
+        enabled: bool,
+    ) -> Result<(), Error> {
+        if idx.instance != self.instance {
+            return Err(Error::WrongInstance);
+        }
+        self.can.txbtie().modify(|w| w.set_tie(idx.idx(), enabled));
+        Ok(())
+    }
+
+    /// Enables or disables the cancellation finished interrupt of one TX buffer (TXBCIE), see
+    /// [set_tx_buffer_interrupt](Self::set_tx_buffer_interrupt).
+    ///
+    /// Only has an effect while the TCF interrupt is enabled.
+    #[inline]
+    pub fn set_tx_buffer_cancel_interrupt(
+        &mut self,
+        idx: TxBufferIdx,
+        enabled: bool,
+    ) -> Result<(), Error> {
+        if idx.instance != self.instance {
+            return Err(Error::WrongInstance);
         }
+        self.can.txbcie().modify(|w| w.set_cf(idx.idx(), enabled));
+        Ok(())
+    }
+
+    /// Same as [write_tx_buffer_pend](Self::write_tx_buffer_pend), but takes `len` data bytes from an iterator,
+    /// so that frames assembled from non-contiguous buffers don't need to be copied to the stack first.
+    ///
+    /// `len` must be a valid CAN (FD) data length, the frame is zero padded if `data` yields fewer bytes.
+    #[cfg(feature = "h7")]
+    pub fn write_tx_buffer_pend_from<I: Iterator<Item = u8>>(
+        &mut self,
+        idx: TxBufferIdx,
+        tx_header: TxFrameHeader,
+        len: usize,
+        data: I,
+    ) -> Result<(), Error> {
+        if idx.instance != self.instance {
+            return Err(Error::WrongInstance);
+        }
+        let tx_buffer = self.message_ram().tx_buffer(idx)?;
+        self.write_tx_element_from(tx_buffer, &tx_header, len, data)?;
 
         // Set as ready to transmit
         _ = self.tx_buffer_pend(idx);
@@ -243,8 +565,105 @@ impl<M: Transmit> FdCan<M> {
         if idx.instance != self.instance {
             return Err(Error::WrongInstance);
         }
-        // Set as ready to transmit
-        self.can.txbar().modify(|w| w.set_ar(idx.idx(), true));
+        self.check_transmit_allowed()?;
+        // Set as ready to transmit. Writing 0 to TXBAR has no effect, so a one-hot write is enough, a
+        // read-modify-write would race with the core clearing bits of requests it has taken over.
+        self.can.txbar().write(|w| w.set_ar(idx.idx(), true));
+        self.tx_staged &= !(1 << idx.idx());
+        Ok(())
+    }
+
+    /// Put a frame into TX FIFO/Queue and set the corresponding "add request" bit.
+    ///
+    /// Returns the index of the buffer that was used, which can be used to track completion or abort the
+    /// transmission, or [Error::WouldBlock] if FIFO/Queue is full.
+    ///
+    /// This is the only way to transmit on chips with a fixed message RAM layout (G0), as they have no dedicated
+    /// TX buffers.
+    pub fn transmit_fifo(
+        &mut self,
+        tx_header: TxFrameHeader,
+        data: &[u8],
+    ) -> Result<TxBufferIdx, Error> {
+        // Fill level and put index must come from the same TXFQS read and must not be cached: buffers can
+        // be freed by the core at any moment (transmission complete, or lost arbitration / error with
+        // automatic retransmission disabled). Put index only advances when an add request is set, so it
+        // stays valid until TXBAR is written below.
+        let txfqs = self.can.txfqs().read();
+        if txfqs.tfqf() {
+            return Err(Error::WouldBlock);
+        }
+        let idx = TxBufferIdx {
+            instance: self.instance,
+            idx: txfqs.tfqpi(),
+        };
+        let tx_buffer = self.message_ram().tx_fifo_buffer(idx)?;
+        self.write_tx_element(tx_buffer, &tx_header, data)?;
+
+        // Set as ready to transmit, see tx_buffer_pend() on why this is not a modify
+        self.can.txbar().write(|w| w.set_ar(idx.idx(), true));
+        Ok(idx)
+    }
+
+    /// Fill TX buffer element header and data, padding the last word with zeroes.
+    fn write_tx_element(
+        &self,
+        tx_buffer: crate::message_ram_layout::TxBufferElement,
+        tx_header: &TxFrameHeader,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        self.write_tx_element_from(tx_buffer, tx_header, data.len(), data.iter().copied())
+    }
+
+    /// Mode types already keep restricted operation and bus monitoring modes from transmitting, this catches an
+    /// instance that got there behind the driver's back, e.g. through an unchecked mode conversion or a direct
+    /// CCCR write. A request would otherwise stay pending forever. Internal loopback sets CCCR.MON as well, but
+    /// does transmit.
+    #[inline]
+    fn check_transmit_allowed(&self) -> Result<(), Error> {
+        let cccr = self.can.cccr().read();
+        if cccr.asm() || (cccr.mon() && !self.can.test().read().lbck()) {
+            return Err(Error::TransmitNotAllowed);
+        }
+        Ok(())
+    }
+
+    /// Writes header and `len` bytes taken from `data` into a TX element, packing them into message RAM words
+    /// directly. If `data` ends early, the rest of the frame is zero padded.
+    fn write_tx_element_from<I: Iterator<Item = u8>>(
+        &self,
+        mut tx_buffer: crate::message_ram_layout::TxBufferElement,
+        tx_header: &TxFrameHeader,
+        len: usize,
+        data: I,
+    ) -> Result<(), Error> {
+        self.check_transmit_allowed()?;
+        let Some(dlc) = Dlc::from_len(len) else {
+            return Err(Error::WrongDataSize);
+        };
+        // Classic frames carry at most 8 bytes whatever the DLC, longer data would be silently truncated
+        let fd = matches!(tx_header.frame_format, FrameFormat::FD);
+        if frame_data_len(dlc.reg_value(), fd) != len {
+            return Err(Error::WrongDataSize);
+        }
+        // Elements of the fixed layout always hold 64 bytes
+        #[cfg(feature = "h7")]
+        if !dlc.fits_in(self.config.layout.tx_buffers_data_size) {
+            return Err(Error::WrongDataSize);
+        }
+
+        tx_buffer.fill(tx_header, dlc);
+
+        let mut data = data.take(len);
+        for d in tx_buffer.data.iter_mut().take(len.div_ceil(4)) {
+            let mut word = [0u8; 4];
+            for (byte, b) in word.iter_mut().zip(&mut data) {
+                *byte = b;
+            }
+            // SAFETY: `d` points into the TX element that was bounds checked by the caller.
+            unsafe { core::ptr::write_volatile(d, u32::from_le_bytes(word)) };
+        }
+
         Ok(())
     }
 
@@ -283,9 +702,20 @@ impl<M: Transmit> FdCan<M> {
     /// If there is a frame in the provided mailbox, and it is canceled successfully, this function
     /// returns `true`.
     ///
-    /// NOTE: Core supports multiple tx buffers abort as well.
+    /// NOTE: Core supports multiple tx buffers abort as well, see [abort_many](Self::abort_many).
     #[inline]
     pub fn abort_blocking(&mut self, idx: TxBufferIdx) -> Result<bool, Error> {
+        self.abort_with_timeout(idx, self.config.timeout_iterations_long)
+    }
+
+    /// Same as [abort_blocking](Self::abort_blocking), but waits at most `timeout_iterations` for the cancellation
+    /// to finish instead of [FdCanConfig::timeout_iterations_long](crate::config::FdCanConfig::timeout_iterations_long).
+    /// About one frame time is enough, as only a transmission that is already ongoing can delay it.
+    pub fn abort_with_timeout(
+        &mut self,
+        idx: TxBufferIdx,
+        timeout_iterations: u32,
+    ) -> Result<bool, Error> {
         if idx.instance != self.instance {
             return Err(Error::WrongInstance);
         }
@@ -295,27 +725,108 @@ impl<M: Transmit> FdCan<M> {
             self.can.txbcr().write(|w| w.set_cr(idx.idx(), true));
 
             // Wait for the abort request to be finished.
-            checked_wait(
-                || self.can.txbcf().read().cf(idx.idx()),
-                self.config.timeout_iterations_long,
-            )?;
+            checked_wait(|| self.can.txbcf().read().cf(idx.idx()), timeout_iterations)?;
             Ok(!self.can.txbto().read().to(idx.idx()))
         } else {
             Ok(false)
         }
     }
 
+    /// Requests cancellation of all buffers set in `mask` (bit n = buffer n) that are currently pending and
+    /// waits for all of them to finish.
+    ///
+    /// Buffers whose transmission was already ongoing when the request landed may still complete successfully,
+    /// such buffers and buffers that were not pending are excluded from the returned mask, only actually
+    /// cancelled buffers are reported.
+    #[inline]
+    pub fn abort_many(&mut self, mask: u32) -> Result<u32, Error> {
+        self.abort_many_with_timeout(mask, self.config.timeout_iterations_long)
+    }
+
+    /// Same as [abort_many](Self::abort_many) with an explicit timeout, see [abort_with_timeout](Self::abort_with_timeout).
+    pub fn abort_many_with_timeout(
+        &mut self,
+        mask: u32,
+        timeout_iterations: u32,
+    ) -> Result<u32, Error> {
+        let pending = self.can.txbrp().read().0 & mask;
+        if pending == 0 {
+            return Ok(0);
+        }
+        self.can.txbcr().write_value(Txbcr(pending));
+
+        // Cancellation finished is set for a buffer both when it was cancelled and when it was transmitted
+        // despite the cancellation request.
+        checked_wait(
+            || self.can.txbcf().read().0 & pending != pending,
+            timeout_iterations,
+        )?;
+        Ok(pending & !self.can.txbto().read().0)
+    }
+
+    /// Cancels everything that is pending in dedicated buffers and FIFO/Queue, for example, before entering
+    /// power down or on emergency stop. See [abort_many](Self::abort_many) for the returned mask.
+    #[inline]
+    pub fn abort_all(&mut self) -> Result<u32, Error> {
+        self.abort_many(u32::MAX)
+    }
+
     #[inline]
     fn has_pending_frame(&self, idx: TxBufferIdx) -> bool {
         self.can.txbrp().read().trp(idx.idx())
     }
 
+    /// Calls `f` for every buffer whose transmission completed since the previous call (TXBTO bit newly set),
+    /// for polling designs that do not want the TX completion interrupt.
+    ///
+    /// TXBTO bits are cleared by hardware when a new request is added for the buffer, so a buffer that is
+    /// re-requested and completes again between two polls is not reported the second time. Poll at least
+    /// once per transmission on each buffer to not miss completions.
+    pub fn on_tx_complete(&mut self, mut f: impl FnMut(TxBufferIdx)) {
+        let completed = self.can.txbto().read().0;
+        let new = completed & !self.tx_completed_seen;
+        self.tx_completed_seen = completed;
+        let mut remaining = new;
+        while remaining != 0 {
+            let idx = remaining.trailing_zeros() as u8;
+            remaining &= remaining - 1;
+            f(TxBufferIdx {
+                instance: self.instance,
+                idx,
+            });
+        }
+    }
+
     /// Returns `true` if no frame is pending for transmission.
+    ///
+    /// A request completes at the end of its frame, a transmit pause that follows it is not waited for, see
+    /// [FdCanConfig::set_transmit_pause](crate::config::FdCanConfig::set_transmit_pause).
     #[inline]
     pub fn is_idle(&self) -> bool {
         self.can.txbrp().read().0 == 0x0
     }
 
+    /// Raw TXBRP value, bit `n` is set while a transmission request for buffer `n` is pending. Dedicated buffers
+    /// come first, followed by FIFO/Queue elements.
+    #[inline]
+    pub fn pending_tx_mask(&self) -> u32 {
+        self.can.txbrp().read().0
+    }
+
+    /// Number of frames pending for transmission, in dedicated buffers and FIFO/Queue alike.
+    #[inline]
+    pub fn pending_tx_count(&self) -> u32 {
+        self.pending_tx_mask().count_ones()
+    }
+
+    /// Waits until all pending transmissions are completed or cancelled (TXBRP == 0), this covers
+    /// dedicated buffers and FIFO/Queue alike. Returns [Error::Timeout] if frames are still pending after
+    /// `timeout_iterations`.
+    #[inline]
+    pub fn flush_blocking(&mut self, timeout_iterations: u32) -> Result<(), Error> {
+        checked_wait(|| !self.is_idle(), timeout_iterations)
+    }
+
     /// Clears the transmission complete flag.
     #[inline]
     pub fn clear_transmission_completed_flag(&mut self) {
@@ -409,3 +920,727 @@ impl<M: Transmit> FdCan<M> {
     //     Mailbox::new(idx)
     // }
 }
+
+impl<M: Receive> FdCan<M> {
+    /// Raw RX FIFO get index (RXFnS.FnGI), i.e. the element that will be read next if FIFO is not empty.
+    #[inline]
+    pub fn rx_fifo_get_index(&self, fifo: FIFONr) -> u8 {
+        self.can.rxfs(fifo.nr()).read().fgi()
+    }
+
+    /// Returns `true` if there are no new elements in the FIFO.
+    #[inline]
+    pub fn rx_fifo_is_empty(&self, fifo: FIFONr) -> bool {
+        self.can.rxfs(fifo.nr()).read().ffl() == 0
+    }
+
+    /// Returns the receive counters collected since the instance was created or [reset](Self::reset_rx_stats).
+    #[cfg(feature = "stats")]
+    #[inline]
+    pub fn rx_stats(&self) -> RxStats {
+        let stats = self.rx_stats;
+        #[cfg(all(feature = "h7", feature = "asynchronous"))]
+        let stats = RxStats {
+            overrun: stats
+                .overrun
+                .wrapping_add(crate::asynchronous::rx_fifo_lost_count(self.instance)),
+            ..stats
+        };
+        stats
+    }
+
+    /// Resets all receive counters to zero.
+    #[cfg(feature = "stats")]
+    #[inline]
+    pub fn reset_rx_stats(&mut self) {
+        self.rx_stats = RxStats::default();
+        #[cfg(all(feature = "h7", feature = "asynchronous"))]
+        crate::asynchronous::reset_rx_fifo_lost_count(self.instance);
+    }
+
+    /// Returns `true` and clears IR.RFnL if a frame was dropped because `fifo` was full in blocking mode since the
+    /// last call, counting it in `RxStats::overrun` with the `stats` feature.
+    ///
+    /// Any number of frames lost in between is reported once. With the `asynchronous` feature the interrupt
+    /// handler services the flag instead, if its interrupt is enabled, and this only sees losses it has not
+    /// serviced yet.
+    #[cfg(feature = "h7")]
+    pub fn take_rx_fifo_lost(&mut self, fifo: FIFONr) -> bool {
+        let lost = self.can.ir().read().rfl(fifo.nr());
+        if lost {
+            self.can.ir().write(|w| w.set_rfl(fifo.nr(), true));
+            #[cfg(feature = "stats")]
+            {
+                self.rx_stats.overrun = self.rx_stats.overrun.wrapping_add(1);
+            }
+        }
+        lost
+    }
+
+    /// Reads the oldest frame from the FIFO into `buf` and acknowledges it.
+    ///
+    /// Returns [Error::WouldBlock] if the FIFO is empty. If `buf` is shorter than the received frame,
+    /// [Error::WrongDataSize] is returned and the frame is left in the FIFO. Any other error releases the element,
+    /// so one unreadable element cannot block the FIFO.
+    pub fn receive_fifo(&mut self, fifo: FIFONr, buf: &mut [u8]) -> Result<RxFrameInfo, Error> {
+        let status = self.can.rxfs(fifo.nr()).read();
+        if status.ffl() == 0 {
+            return Err(Error::WouldBlock);
+        }
+        let idx = status.fgi();
+        let result = self.read_rx_fifo_element(fifo, idx, buf);
+        if !matches!(result, Err(Error::WrongDataSize)) {
+            self.acknowledge_rx_fifo(fifo, idx);
+        }
+        result
+    }
+
+    /// Waits for a frame to arrive in the FIFO and reads it, see [receive_fifo](Self::receive_fifo).
+    ///
+    /// Returns [Error::Timeout] if the FIFO is still empty after `timeout_iterations`, e.g. in loopback bring-up
+    /// when a filter rejects the looped back frame.
+    pub fn receive_blocking(
+        &mut self,
+        fifo: FIFONr,
+        buf: &mut [u8],
+        timeout_iterations: u32,
+    ) -> Result<RxFrameInfo, Error> {
+        checked_wait(|| self.rx_fifo_is_empty(fifo), timeout_iterations)?;
+        self.receive_fifo(fifo, buf)
+    }
+
+    /// Acknowledges the element at `idx`, which must be the get index read from RXFnS for this very element.
+    ///
+    /// Writing FAI moves the get index to `idx + 1` and releases every element up to it, so a stale index
+    /// (e.g. the element was already acknowledged from an interrupt handler) would release unread frames and
+    /// corrupt the fill level. The get index is re-read and a stale acknowledge is ignored, whoever moved the get
+    /// index already released the element.
+    pub(crate) fn acknowledge_rx_fifo(&mut self, fifo: FIFONr, idx: u8) {
+        self.acknowledge_rx_fifo_through(fifo, idx, idx);
+    }
+
+    /// Releases every element from `first`, the get index the batch was read from, up to and including `last`
+    /// with a single FAI write. Same stale index check as [acknowledge_rx_fifo](Self::acknowledge_rx_fifo).
+    fn acknowledge_rx_fifo_through(&mut self, fifo: FIFONr, first: u8, last: u8) {
+        let status = self.can.rxfs(fifo.nr()).read();
+        if status.ffl() > 0 && status.fgi() == first {
+            self.can.rxfa(fifo.nr()).write(|w| w.set_fai(last));
+        }
+    }
+
+    /// Reads up to `max` frames from the FIFO, passing each one to `f`, and releases all of them at once.
+    ///
+    /// FAI acknowledges every element up to and including the written index, so the batch is freed with a single
+    /// write of `(get_index + count - 1) % fifo_len` instead of one acknowledge per frame. The elements stay
+    /// occupied until all of them were handed to `f`, keep `f` short if the FIFO is close to full. Elements that
+    /// cannot be read are released with the batch without being passed to `f`. Returns the number of elements
+    /// released, 0 if the FIFO is empty.
+    pub fn drain_fifo<F: FnMut(RxFrame)>(&mut self, fifo: FIFONr, max: usize, mut f: F) -> usize {
+        let status = self.can.rxfs(fifo.nr()).read();
+        let fifo_len = self.message_ram().rx_fifo_len(fifo) as usize;
+        let count = (status.ffl() as usize).min(max);
+        if count == 0 || fifo_len == 0 {
+            return 0;
+        }
+        let first = status.fgi();
+        let mut read = 0;
+        while read < count {
+            let idx = fifo_index(first, read, fifo_len);
+            let mut data = [0u8; 64];
+            if let Ok(info) = self.read_rx_fifo_element(fifo, idx, &mut data) {
+                f(RxFrame { info, data });
+            }
+            read += 1;
+        }
+        if read > 0 {
+            let last = fifo_index(first, read - 1, fifo_len);
+            self.acknowledge_rx_fifo_through(fifo, first, last);
+        }
+        read
+    }
+
+    /// Returns `true` once an FD frame was read through this driver, from a FIFO or a dedicated RX buffer.
+    ///
+    /// Together with [fd_tx_completed](Self::fd_tx_completed) this lets an application that enabled FD
+    /// transmission decide at runtime whether the bus carries FD traffic or it should fall back to classic
+    /// frames. The flag is sticky and survives mode changes.
+    #[inline]
+    pub fn fd_seen(&self) -> bool {
+        self.fd_rx_seen
+    }
+
+    /// Returns an iterator over all frames currently available in the FIFO, see [FrameIter].
+    #[inline]
+    pub fn frames(&mut self, fifo: FIFONr) -> FrameIter<'_, M> {
+        FrameIter {
+            can: self,
+            fifo,
+            unacknowledged: None,
+        }
+    }
+
+    /// Same as [frames](Self::frames), but only yields frames for which `pred` returns `true`, errors are
+    /// always yielded.
+    ///
+    /// Every element is still acknowledged, so the FIFO keeps draining while software filtering drops part of
+    /// the traffic that hardware filters let through.
+    #[inline]
+    pub fn frames_matching<F: Fn(&RxFrameInfo) -> bool>(
+        &mut self,
+        fifo: FIFONr,
+        pred: F,
+    ) -> impl Iterator<Item = Result<RxFrame, Error>> {
+        self.frames(fifo)
+            .filter(move |frame| frame.as_ref().map_or(true, |frame| pred(&frame.info)))
+    }
+
+    /// Bitmap of dedicated RX buffers holding a frame that was not read yet, bit `n` is buffer `n`.
+    ///
+    /// Both NDAT registers are read at once, so scanning the set bits and calling [Self::read_rx_buffer] on
+    /// each avoids polling buffers one by one.
+    #[cfg(feature = "h7")]
+    #[inline]
+    pub fn new_data_mask(&self) -> u64 {
+        let low = self.can.ndat1().read().nd() as u64;
+        let high = self.can.ndat2().read().nd() as u64;
+        high << 32 | low
+    }
+
+    /// Reads the frame from dedicated RX buffer `idx` into `buf` and clears its new data flag.
+    ///
+    /// Returns [Error::WouldBlock] if the buffer holds no new frame. If `buf` is shorter than the received
+    /// frame, [Error::WrongDataSize] is returned and the new data flag is left set.
+    /// See [read_rx_buffer_checked](Self::read_rx_buffer_checked) to learn about a frame arriving right after.
+    #[cfg(feature = "h7")]
+    #[inline]
+    pub fn read_rx_buffer(&mut self, idx: u8, buf: &mut [u8]) -> Result<RxFrameInfo, Error> {
+        self.read_rx_buffer_checked(idx, buf).map(|(info, _)| info)
+    }
+
+    /// Same as [read_rx_buffer](Self::read_rx_buffer), additionally returns `true` if a new frame was already
+    /// stored into the same buffer by the time its new data flag was cleared.
+    ///
+    /// The element is read completely before the flag is cleared, and only this buffer's flag is written (NDAT
+    /// is write-1-to-clear). While the flag is set, filters referencing the buffer do not match, so the core
+    /// never overwrites an unread frame. Once it is cleared, the next frame may land immediately, the flag is
+    /// re-read to report exactly that case, so the caller can read the buffer again instead of waiting for the
+    /// next scan of [new_data_mask](Self::new_data_mask).
+    #[cfg(feature = "h7")]
+    pub fn read_rx_buffer_checked(
+        &mut self,
+        idx: u8,
+        buf: &mut [u8],
+    ) -> Result<(RxFrameInfo, bool), Error> {
+        let element = self.message_ram().rx_buffer_element(idx)?;
+        let bit = 1u64 << idx;
+        if self.new_data_mask() & bit == 0 {
+            return Err(Error::WouldBlock);
+        }
+        let info = Self::read_rx_element(element, buf)?;
+        self.record_rx(None, &info);
+        // Flags are cleared by writing 1, zeroes leave other buffers untouched
+        if idx < 32 {
+            self.can.ndat1().write_value(Ndat1(bit as u32));
+        } else {
+            self.can.ndat2().write_value(Ndat2((bit >> 32) as u32));
+        }
+        let new_frame = self.new_data_mask() & bit != 0;
+        Ok((info, new_frame))
+    }
+
+    fn read_rx_fifo_element(
+        &mut self,
+        fifo: FIFONr,
+        idx: u8,
+        buf: &mut [u8],
+    ) -> Result<RxFrameInfo, Error> {
+        let element = self.message_ram().rx_fifo_element(fifo, idx)?;
+        let info = Self::read_rx_element(element, buf)?;
+        self.record_rx(Some(fifo), &info);
+        Ok(info)
+    }
+
+    /// Notes a frame read from `fifo`, or from a dedicated RX buffer if `None`.
+    fn record_rx(&mut self, fifo: Option<FIFONr>, info: &RxFrameInfo) {
+        if matches!(info.frame_format, FrameFormat::FD) {
+            self.fd_rx_seen = true;
+        }
+        #[cfg(feature = "stats")]
+        self.count_rx(fifo, info);
+        #[cfg(not(feature = "stats"))]
+        let _ = fifo;
+    }
+
+    #[cfg(feature = "stats")]
+    fn count_rx(&mut self, fifo: Option<FIFONr>, info: &RxFrameInfo) {
+        let stats = &mut self.rx_stats;
+        let counter = match fifo {
+            Some(FIFONr::FIFO0) => &mut stats.fifo0,
+            Some(FIFONr::FIFO1) => &mut stats.fifo1,
+            None => &mut stats.dedicated,
+        };
+        *counter = counter.wrapping_add(1);
+        if info.matched_filter.is_none() {
+            stats.non_matching = stats.non_matching.wrapping_add(1);
+        }
+    }
+
+    /// Copies the element into `buf`, only fails with [Error::WrongDataSize] if `buf` is too short.
+    ///
+    /// A frame longer than the element's data field was cut off by the core when it was stored, it is returned
+    /// with what was stored and [RxFrameInfo::truncated] set: it will never fit, so it must not block the buffer.
+    fn read_rx_element(element: RxBufferElement, buf: &mut [u8]) -> Result<RxFrameInfo, Error> {
+        let mut info = RxFrameInfo::decode(element.r0.read(), element.r1.read());
+        let stored = element.data.len() * 4;
+        if stored < info.len as usize {
+            info.len = stored as u8;
+            info.truncated = true;
+        }
+        let len = info.len as usize;
+        if buf.len() < len {
+            return Err(Error::WrongDataSize);
+        }
+        for (chunk, word) in buf[..len].chunks_mut(4).zip(element.data.iter()) {
+            let word = unsafe { core::ptr::read_volatile(word) }.to_le_bytes();
+            chunk.copy_from_slice(&word[..chunk.len()]);
+        }
+        Ok(info)
+    }
+}
+
+/// Iterator over frames in RX FIFO0 or FIFO1, yields until the FIFO is empty.
+///
+/// Each yielded element is acknowledged exactly once: when the next one is requested or when the iterator is
+/// dropped, so breaking out of a `for` loop early does not leave an already consumed frame in the FIFO. An
+/// element that cannot be read is yielded as an error and acknowledged the same way, iteration continues with
+/// the next one.
+pub struct FrameIter<'a, M: Receive> {
+    can: &'a mut FdCan<M>,
+    fifo: FIFONr,
+    /// Get index of the element that was yielded but not yet acknowledged
+    unacknowledged: Option<u8>,
+}
+
+impl<M: Receive> FrameIter<'_, M> {
+    fn acknowledge(&mut self) {
+        if let Some(idx) = self.unacknowledged.take() {
+            self.can.acknowledge_rx_fifo(self.fifo, idx);
+        }
+    }
+}
+
+impl<M: Receive> Iterator for FrameIter<'_, M> {
+    type Item = Result<RxFrame, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.acknowledge();
+        let status = self.can.can.rxfs(self.fifo.nr()).read();
+        if status.ffl() == 0 {
+            return None;
+        }
+        let idx = status.fgi();
+        let mut data = [0u8; 64];
+        let info = self.can.read_rx_fifo_element(self.fifo, idx, &mut data);
+        self.unacknowledged = Some(idx);
+        Some(info.map(|info| RxFrame { info, data }))
+    }
+}
+
+impl<M: Receive> Drop for FrameIter<'_, M> {
+    fn drop(&mut self) {
+        self.acknowledge();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::fdcan::NormalOperationMode;
+    use crate::pac_traits::Reg;
+    use std::boxed::Box;
+
+    /// RX element backed by leaked host memory: R0, R1 and `data_words` data words.
+    fn rx_element(r0: u32, r1: u32, data_words: usize) -> RxBufferElement {
+        let mut words = std::vec![0u32; 2 + data_words];
+        words[0] = r0;
+        words[1] = r1;
+        for (i, word) in words[2..].iter_mut().enumerate() {
+            *word = u32::from_le_bytes([
+                4 * i as u8,
+                4 * i as u8 + 1,
+                4 * i as u8 + 2,
+                4 * i as u8 + 3,
+            ]);
+        }
+        let ptr = Box::leak(words.into_boxed_slice()).as_mut_ptr();
+        // SAFETY: the words are leaked, so they outlive the element, and hold R0, R1 and the data field.
+        unsafe {
+            RxBufferElement {
+                r0: Reg::from_ptr(ptr as *mut _),
+                r1: Reg::from_ptr(ptr.add(1) as *mut _),
+                data: core::slice::from_raw_parts(ptr.add(2), data_words),
+            }
+        }
+    }
+
+    fn read(element: RxBufferElement, buf: &mut [u8]) -> Result<RxFrameInfo, Error> {
+        FdCan::<NormalOperationMode>::read_rx_element(element, buf)
+    }
+
+    fn fd_r1(dlc: u8) -> u32 {
+        RxBufferElementR1::new()
+            .with_fdf(FrameFormat::FD)
+            .with_dlc(dlc)
+            .into_bits()
+    }
+
+    #[test]
+    fn fd_brs_header_with_standard_id() {
+        use crate::StandardId;
+        use crate::message_ram_layout::TxBufferElement;
+        use crate::pac::message_ram::{EventFIFOControl, Rtr, Xtd};
+
+        let header = TxFrameHeader::fd_brs(StandardId::new(0x123).unwrap().into());
+        let (t0, t1) = TxBufferElement::encode_tx_header(&header, Dlc::from_len(64).unwrap());
+        assert!(matches!(t0.xtd(), Xtd::ElevenBits));
+        assert!(matches!(t0.rtr(), Rtr::TransmitDataFrame));
+        assert_eq!(t0.into_bits() & 0x1FFF_FFFF, 0x123 << 18);
+        assert!(matches!(t1.fdf(), FrameFormat::FD));
+        assert!(matches!(t1.brs(), BitRateSwitch::Switch));
+        assert!(matches!(t1.efc(), EventFIFOControl::DontStoreTxEvents));
+        assert_eq!(t1.dlc(), 15);
+    }
+
+    #[test]
+    fn classic_frame_ignores_fd_only_bits() {
+        use crate::StandardId;
+
+        let r0 = RxBufferElementR0::new()
+            .with_esi(Esi::EsiTransmittedRecessive)
+            .with_id(0x123 << 18);
+        let r1 = RxBufferElementR1::new()
+            .with_fdf(FrameFormat::Classic)
+            .with_brs(BitRateSwitch::Switch)
+            .with_dlc(8)
+            .with_fidx(3)
+            .with_rxts(0x1234);
+        let mut buf = [0u8; 8];
+        let info = read(rx_element(r0.into_bits(), r1.into_bits(), 2), &mut buf).unwrap();
+        assert!(matches!(info.frame_format, FrameFormat::Classic));
+        assert_eq!(info.id, Id::Standard(StandardId::new(0x123).unwrap()));
+        assert!(!info.remote);
+        assert!(!info.bit_rate_switching);
+        assert!(matches!(info.error_state, Esi::EsiDependsOnErrorPassive));
+        assert_eq!(info.len, 8);
+        assert_eq!(info.timestamp, 0x1234);
+        assert_eq!(info.matched_filter, Some(3));
+        assert_eq!(buf, [0, 1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn fd_brs_frame_decodes() {
+        use crate::ExtendedId;
+        use crate::pac::message_ram::Xtd;
+
+        let r0 = RxBufferElementR0::new()
+            .with_esi(Esi::EsiTransmittedRecessive)
+            .with_xtd(Xtd::TwentyNineBits)
+            .with_id(0x1ABC_DEF0);
+        let r1 = RxBufferElementR1::new()
+            .with_fdf(FrameFormat::FD)
+            .with_brs(BitRateSwitch::Switch)
+            .with_dlc(9)
+            .with_anmf(true);
+        let mut buf = [0u8; 64];
+        let info = read(rx_element(r0.into_bits(), r1.into_bits(), 3), &mut buf).unwrap();
+        assert!(matches!(info.frame_format, FrameFormat::FD));
+        assert_eq!(info.id, Id::Extended(ExtendedId::new(0x1ABC_DEF0).unwrap()));
+        assert!(info.bit_rate_switching);
+        assert!(matches!(info.error_state, Esi::EsiTransmittedRecessive));
+        assert_eq!(info.len, 12);
+        assert!(!info.truncated);
+        assert_eq!(info.matched_filter, None);
+        assert_eq!(buf[..12], [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
+    }
+
+    #[test]
+    fn tx_header_round_trips_through_rx_decode() {
+        use crate::ExtendedId;
+        use crate::message_ram_layout::TxBufferElement;
+
+        let id = Id::Extended(ExtendedId::new(0x0ABC_1234).unwrap());
+        for (header, len) in [
+            (TxFrameHeader::new(id), 8),
+            (TxFrameHeader::new(id).fd(), 20),
+            (TxFrameHeader::fd_brs(id).esi_recessive(), 64),
+        ] {
+            let (t0, t1) = TxBufferElement::encode_tx_header(&header, Dlc::from_len(len).unwrap());
+            // What the core stores on reception: T0 and R0 share their layout, FDF, BRS and DLC sit at the same
+            // positions in T1 and R1
+            let r0 = RxBufferElementR0::from_bits(t0.into_bits());
+            let r1 = RxBufferElementR1::new()
+                .with_fdf(t1.fdf())
+                .with_brs(t1.brs())
+                .with_dlc(t1.dlc());
+            let info = RxFrameInfo::decode(r0, r1);
+            assert_eq!(info.id, header.id);
+            assert_eq!(
+                matches!(info.frame_format, FrameFormat::FD),
+                matches!(header.frame_format, FrameFormat::FD)
+            );
+            assert_eq!(info.bit_rate_switching, header.bit_rate_switching);
+            assert_eq!(
+                matches!(info.error_state, Esi::EsiTransmittedRecessive),
+                matches!(header.error_state, Esi::EsiTransmittedRecessive)
+            );
+            assert_eq!(info.len as usize, len);
+        }
+    }
+
+    #[cfg(feature = "h7")]
+    #[test]
+    fn transmit_is_refused_in_non_transmitting_modes() {
+        use crate::StandardId;
+
+        let mut can = fifo_can::<NormalOperationMode>(0, 1);
+        let header = TxFrameHeader::new(StandardId::new(0x123).unwrap().into());
+        let regs = can.can;
+        // Restricted operation, then bus monitoring
+        regs.cccr().modify(|w| w.set_asm(true));
+        assert!(matches!(
+            can.transmit_fifo(header, &[]),
+            Err(Error::TransmitNotAllowed)
+        ));
+        regs.cccr().modify(|w| {
+            w.set_asm(false);
+            w.set_mon(true);
+        });
+        assert!(matches!(
+            can.transmit_fifo(header, &[]),
+            Err(Error::TransmitNotAllowed)
+        ));
+        // Internal loopback sets MON too, but transmits
+        regs.test().modify(|w| w.set_lbck(true));
+        assert!(can.transmit_fifo(header, &[]).is_ok());
+    }
+
+    #[test]
+    fn batch_acknowledge_index_wraps() {
+        // 3 elements read from a 4 element FIFO starting at get index 2 end at index 0
+        assert_eq!(fifo_index(2, 3 - 1, 4), 0);
+        assert_eq!(fifo_index(0, 4 - 1, 4), 3);
+        assert_eq!(fifo_index(63, 0, 64), 63);
+    }
+
+    /// Instance on host RAM with nothing but an RX FIFO0 and a TX FIFO, both with 8 byte elements.
+    #[cfg(feature = "h7")]
+    fn fifo_can<M>(rx_fifo_len: u8, tx_fifo_len: u8) -> FdCan<M> {
+        use crate::FdCanInstance;
+        use crate::fdcan::tests::host_fdcan;
+        use crate::message_ram_builder::MessageRamBuilder;
+        use crate::message_ram_layout::tests::{fifo_layout, host_ram};
+
+        let builder = MessageRamBuilder::for_region(0, 0x400, FdCanInstance::FdCan1);
+        let (layout, _) = fifo_layout(builder, 0, rx_fifo_len, tx_fifo_len, 0);
+        let mut can = host_fdcan(FdCanInstance::FdCan1, host_ram());
+        can.config.layout = layout;
+        can
+    }
+
+    #[cfg(feature = "h7")]
+    #[test]
+    fn rx_fifo_lost_is_taken_once_per_flag() {
+        use crate::pac::registers::regs::Ir;
+
+        let mut can = fifo_can::<NormalOperationMode>(4, 0);
+        let mut lost = Ir(0);
+        lost.set_rfl(1, true);
+        can.can.ir().write_value(lost);
+
+        assert!(!can.take_rx_fifo_lost(FIFONr::FIFO0));
+        assert!(can.take_rx_fifo_lost(FIFONr::FIFO1));
+        // IR is write 1 to clear, only RF1L was written back
+        assert_eq!(can.can.ir().read(), lost);
+        #[cfg(feature = "stats")]
+        assert_eq!(can.rx_stats().overrun, 1);
+
+        // What the core does on that write
+        can.can.ir().write_value(Ir(0));
+        assert!(!can.take_rx_fifo_lost(FIFONr::FIFO1));
+        #[cfg(feature = "stats")]
+        assert_eq!(can.rx_stats().overrun, 1);
+    }
+
+    #[cfg(feature = "h7")]
+    #[test]
+    fn fill_level_drops_by_the_acknowledged_count() {
+        use crate::pac::registers::regs::{Rxfa, Rxfs};
+
+        const FIFO_LEN: u8 = 4;
+        // Out of range for a 4 element FIFO, marks that FAI was not written
+        const NO_ACK: Rxfa = Rxfa(0x3F);
+        let mut can = fifo_can::<NormalOperationMode>(FIFO_LEN, 0);
+        let regs = can.can;
+        let set_status = |ffl, fgi| {
+            let mut status = Rxfs::default();
+            status.set_ffl(ffl);
+            status.set_fgi(fgi);
+            regs.rxfs(0).write_value(status);
+            regs.rxfa(0).write_value(NO_ACK);
+        };
+        // What the core does on an FAI write: release every element from the get index up to FAI
+        let apply_ack = || {
+            let ack = regs.rxfa(0).read();
+            if ack == NO_ACK {
+                return 0;
+            }
+            let mut status = regs.rxfs(0).read();
+            let released = (ack.fai() + FIFO_LEN - status.fgi()) % FIFO_LEN + 1;
+            status.set_ffl(status.ffl() - released);
+            status.set_fgi((ack.fai() + 1) % FIFO_LEN);
+            regs.rxfs(0).write_value(status);
+            regs.rxfa(0).write_value(NO_ACK);
+            released
+        };
+        let fill_level = || regs.rxfs(0).read().ffl();
+
+        // Element 2 was read here, but an interrupt handler read and acknowledged it first
+        set_status(3, 3);
+        can.acknowledge_rx_fifo(FIFONr::FIFO0, 2);
+        assert_eq!(apply_ack(), 0);
+        assert_eq!(fill_level(), 3);
+
+        can.receive_fifo(FIFONr::FIFO0, &mut [0u8; 8]).unwrap();
+        assert_eq!(apply_ack(), 1);
+        assert_eq!(fill_level(), 2);
+
+        // The batch wraps from index 0 to 1
+        assert_eq!(can.drain_fifo(FIFONr::FIFO0, 8, |_| {}), 2);
+        assert_eq!(apply_ack(), 2);
+        assert_eq!(fill_level(), 0);
+        assert!(matches!(
+            can.receive_fifo(FIFONr::FIFO0, &mut [0u8; 8]),
+            Err(Error::WouldBlock)
+        ));
+    }
+
+    #[cfg(feature = "h7")]
+    #[test]
+    fn one_shot_fifo_puts_never_reuse_a_pending_buffer() {
+        use crate::StandardId;
+        use crate::fdcan::InternalLoopbackMode;
+        use crate::pac::registers::regs::{Txbar, Txbrp, Txfqs};
+
+        const FIFO_LEN: u8 = 8;
+        let mut can = fifo_can::<InternalLoopbackMode>(0, FIFO_LEN);
+        let regs = can.can;
+        // Core side of the TX FIFO: requests are taken over from TXBAR, and with automatic retransmission
+        // disabled buffers are freed after a single attempt, successful or not.
+        let (mut pending, mut put, mut get, mut fill) = (0u32, 0u8, 0u8, 0u8);
+        let (mut accepted, mut rejected) = (0, 0);
+        let mut seed = 0x1234_5678u32;
+        for n in 0..10_000u32 {
+            let header = TxFrameHeader::new(StandardId::new((n % 0x800) as u16).unwrap().into());
+            match can.transmit_fifo(header, &[n as u8]) {
+                Ok(idx) => {
+                    let requested = regs.txbar().read().0;
+                    assert_eq!(requested, 1 << idx.idx());
+                    assert_eq!(
+                        pending & requested,
+                        0,
+                        "buffer {} reused while pending",
+                        idx.idx()
+                    );
+                    pending |= requested;
+                    regs.txbar().write_value(Txbar(0));
+                    put = (put + 1) % FIFO_LEN;
+                    fill += 1;
+                    accepted += 1;
+                }
+                Err(Error::WouldBlock) => {
+                    assert_eq!(fill, FIFO_LEN);
+                    rejected += 1;
+                }
+                Err(e) => panic!("unexpected {e:?}"),
+            }
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            for _ in 0..seed % 3 {
+                if fill > 0 {
+                    pending &= !(1 << get);
+                    get = (get + 1) % FIFO_LEN;
+                    fill -= 1;
+                }
+            }
+            let mut status = Txfqs::default();
+            status.set_tffl(FIFO_LEN - fill);
+            status.set_tfgi(get);
+            status.set_tfqpi(put);
+            status.set_tfqf(fill == FIFO_LEN);
+            regs.txfqs().write_value(status);
+            regs.txbrp().write_value(Txbrp(pending));
+        }
+        assert!(accepted > 0 && rejected > 0);
+    }
+
+    #[test]
+    fn frame_longer_than_element_is_truncated() {
+        // 64 byte FD frame stored into an element with an 8 byte data field
+        let element = rx_element(0, fd_r1(15), 2);
+        let mut buf = [0u8; 64];
+        let info = read(element, &mut buf).unwrap();
+        assert!(info.truncated);
+        assert_eq!(info.len, 8);
+        assert_eq!(buf[..8], [0, 1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn short_buffer_is_retryable() {
+        let element = rx_element(0, fd_r1(8), 2);
+        assert!(matches!(
+            read(element, &mut [0u8; 4]),
+            Err(Error::WrongDataSize)
+        ));
+        let element = rx_element(0, fd_r1(8), 2);
+        let info = read(element, &mut [0u8; 8]).unwrap();
+        assert!(!info.truncated);
+        assert_eq!(info.len, 8);
+    }
+
+    #[test]
+    fn dlc_maps_to_the_same_length_for_tx_and_rx() {
+        // 0..=8 are literal, 9..=15 are 8 bytes for classic frames and 12/16/20/24/32/48/64 bytes for FD frames.
+        // Each FD length encodes back to its DLC.
+        const FD_LEN: [usize; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 12, 16, 20, 24, 32, 48, 64];
+        for dlc in 0..16u8 {
+            let classic = if dlc <= 8 { dlc as usize } else { 8 };
+            assert_eq!(frame_data_len(dlc, false), classic);
+            let fd = frame_data_len(dlc, true);
+            assert_eq!(fd, FD_LEN[dlc as usize]);
+            assert_eq!(Dlc::from_len(fd).unwrap().reg_value(), dlc);
+        }
+    }
+
+    #[test]
+    fn header_builder_matches_struct_literal_defaults() {
+        use crate::StandardId;
+
+        // The builder must end up with the same header as the struct literal users wrote before it existed
+        let id = Id::Standard(StandardId::MAX);
+        let h = TxFrameHeader::new(id);
+        assert!(matches!(h.frame_format, FrameFormat::Classic));
+        assert!(!h.bit_rate_switching);
+        assert_eq!(h.marker, None);
+        assert!(!h.timestamp_capture);
+        assert_eq!(TxFrameHeader::new(id).store_tx_event().marker, Some(0));
+        let h = TxFrameHeader::new(id).marker(7).store_tx_event();
+        assert_eq!(h.marker, Some(7));
+        let h = TxFrameHeader::fd_brs(id).esi_recessive();
+        assert!(matches!(h.frame_format, FrameFormat::FD));
+        assert!(h.bit_rate_switching);
+        assert!(matches!(h.error_state, Esi::EsiTransmittedRecessive));
+    }
+}
@@ -1,7 +1,10 @@
 use crate::fdcan::Error;
 
 #[inline]
-pub(crate) fn checked_wait<F: Fn() -> bool>(f: F, timeout_iterations: u32) -> Result<(), Error> {
+pub(crate) fn checked_wait<F: FnMut() -> bool>(
+    mut f: F,
+    timeout_iterations: u32,
+) -> Result<(), Error> {
     let mut elapsed = 0;
     while f() {
         elapsed += 1;